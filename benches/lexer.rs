@@ -0,0 +1,18 @@
+use std::time::Instant;
+
+use suro::token::tokenize;
+
+const RUNS: u32 = 10;
+
+/// tokenizes a large synthetic program several times and reports the average time per run
+fn main() {
+    let line = "    set total to call add with (total, 12.5 * x - 3); if total >= 100 then { change total to 0 }; -- reset\n";
+    let source = format!("{{\n{}}}", line.repeat(5_000));
+    let tokens = tokenize(&source).unwrap().len();
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        tokenize(&source).unwrap();
+    }
+    let per_run = start.elapsed() / RUNS;
+    println!("lexer: {} bytes, {} tokens, {:?} per run", source.len(), tokens, per_run);
+}
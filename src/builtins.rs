@@ -1,11 +1,98 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::env;
+use std::mem;
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
 
+use regex::Regex;
+
+use crate::interpreter::{Interpreter, RuntimeError};
 use crate::object::Object;
+use crate::parser::{CompOp, ExprOp, TermOp};
 
 pub fn get_builtins() -> Vec<(String, Object)> {
     vec![
         get_builtin("print", s_print),
         get_builtin("to_bool", s_to_bool),
+        get_builtin("bool_to_int", s_bool_to_int),
+        get_builtin("int_to_bool", s_int_to_bool),
+        get_builtin("sleep", s_sleep),
+        get_builtin("op", s_op),
+        get_builtin("string_to_chars", s_string_to_chars),
+        get_builtin("chars_to_string", s_chars_to_string),
+        get_builtin("char_range", s_char_range),
+        get_builtin("iota", s_iota),
+        get_builtin("counts", s_counts),
+        get_builtin("normalize_whitespace", s_normalize_whitespace),
+        get_builtin("trim_chars", s_trim_chars),
+        get_builtin("trim_start", s_trim_start),
+        get_builtin("trim_end", s_trim_end),
+        get_builtin("pad_left", s_pad_left),
+        get_builtin("pad_right", s_pad_right),
+        get_builtin("any_starts_with", s_any_starts_with),
+        get_builtin("filter_prefix", s_filter_prefix),
+        get_builtin("args", s_args),
+        get_builtin("os", s_os),
+        get_builtin("arch", s_arch),
+        get_builtin("in_range", s_in_range),
+        get_builtin("clamp_report", s_clamp_report),
+        get_builtin("mean", s_mean),
+        get_builtin("median", s_median),
+        get_builtin("mode", s_mode),
+        get_builtin("argmin", s_argmin),
+        get_builtin("argmax", s_argmax),
+        get_builtin("scan", s_scan),
+        get_builtin("gcd", s_gcd),
+        get_builtin("lcm", s_lcm),
+        get_builtin("get_or", s_get_or),
+        get_builtin("deep_equals", s_deep_equals),
+        get_builtin("group_digits", s_group_digits),
+        get_builtin("get_path", s_get_path),
+        get_builtin("set_path", s_set_path),
+        get_builtin("flatten_map", s_flatten_map),
+        get_builtin("merge", s_merge),
+        get_builtin("timestamp_to_parts", s_timestamp_to_parts),
+        get_builtin("parts_to_timestamp", s_parts_to_timestamp),
+        get_builtin("chunk", s_chunk),
+        get_builtin("rotate", s_rotate),
+        get_builtin("resize", s_resize),
+        get_builtin("swap", s_swap),
+        get_builtin("combinations", s_combinations),
+        get_builtin("permutations", s_permutations),
+        get_builtin("zip_longest", s_zip_longest),
+        get_builtin("zip_to_map", s_zip_to_map),
+        get_builtin("difference", s_difference),
+        get_builtin("intersection", s_intersection),
+        get_builtin("union", s_union),
+        get_builtin("is_sorted", s_is_sorted),
+        get_builtin("size_of", s_size_of),
+        get_builtin("to_base64", s_to_base64),
+        get_builtin("from_base64", s_from_base64),
+        get_builtin("to_hex_string", s_to_hex_string),
+        get_builtin("from_hex_string", s_from_hex_string),
+        get_builtin("hash", s_hash),
+        get_builtin("interpolate", s_interpolate),
+        get_builtin("sorted_keys", s_sorted_keys),
+        get_builtin("coalesce", s_coalesce),
+        get_builtin("memoize", s_memoize),
+        get_builtin("generate", s_generate),
+        get_builtin("is_callable", s_is_callable),
+        get_builtin("type", s_type),
+        get_builtin("assert_type", s_assert_type),
+        get_builtin("assert_throws", s_assert_throws),
+        get_builtin("stack_depth", s_stack_depth),
+        get_builtin("node_count", s_node_count),
+        get_builtin("lines", s_lines),
+        get_builtin("format_table", s_format_table),
+        get_builtin("matches", s_matches),
+        get_builtin("find_all", s_find_all),
+        get_builtin("regex_replace", s_regex_replace),
+        get_builtin("regex_split", s_regex_split),
+        get_builtin("settings", s_settings),
+        get_builtin("pretty", s_pretty),
     ]
 }
 
@@ -15,34 +102,1813 @@ pub fn fill_with_builtins(map: &mut HashMap<String, Object>) {
     }
 }
 
-fn get_builtin(name: &str, func: fn(Vec<Object>) -> Object) -> (String, Object) {
+fn get_builtin(name: &str, func: fn(&mut Interpreter, Vec<Object>) -> Result<Object, RuntimeError>) -> (String, Object) {
     (name.to_string(), Object::RustFunction(func))
 }
 
-fn s_print(args: Vec<Object>) -> Object {
+fn s_print(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
     for obj in args {
-        match obj {
-            Object::String(string) => println!("{}", string),
-            Object::Integer(num) => println!("{}", num),
-            Object::Boolean(val) => println!("{}", val),
-            obj => panic!("Invalid argument for s_print: {:?}", obj),
+        println!("{}", obj);
+    }
+    Ok(Object::Null)
+}
+
+fn s_to_bool(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    if args.len() != 1 {
+        return Err(RuntimeError::new("Must supply only one argument to s_to_bool"));
+    }
+    Ok(Object::Boolean(to_bool(args.first().unwrap())?))
+}
+
+fn s_bool_to_int(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::Boolean(val)] => Object::Integer(*val as i32),
+        _ => return Err(RuntimeError::new(format!("s_bool_to_int expects a single boolean, got {:?}", args))),
+    })
+}
+
+/// unlike to_bool, only accepts integers
+fn s_int_to_bool(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::Integer(num)] => Object::Boolean(*num != 0),
+        _ => return Err(RuntimeError::new(format!("s_int_to_bool expects a single integer, got {:?}", args))),
+    })
+}
+
+fn s_sleep(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    if args.len() != 1 {
+        return Err(RuntimeError::new("Must supply only one argument to s_sleep"));
+    }
+    match args.first().unwrap() {
+        Object::Integer(millis) if *millis < 0 => {
+            return Err(RuntimeError::new(format!("Cannot sleep for a negative duration: {}", millis)));
         }
+        Object::Integer(millis) => thread::sleep(Duration::from_millis(*millis as u64)),
+        obj => return Err(RuntimeError::new(format!("Invalid argument for s_sleep: {:?}", obj))),
+    }
+    Ok(Object::Null)
+}
+
+/// applies the binary operator named by the first argument to the other two
+fn s_op(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    if args.len() != 3 {
+        return Err(RuntimeError::new("Must supply exactly three arguments to s_op"));
+    }
+    let mut args = args.into_iter();
+    let (symbol, left, right) = (args.next().unwrap(), args.next().unwrap(), args.next().unwrap());
+    match symbol {
+        Object::String(symbol) => match symbol.as_str() {
+            "+" => Interpreter::eval_exprop(&ExprOp::Add, left, right),
+            "-" => Interpreter::eval_exprop(&ExprOp::Sub, left, right),
+            "*" => Interpreter::eval_termop(&TermOp::Mul, left, right),
+            "/" => Interpreter::eval_termop(&TermOp::Div, left, right),
+            "%" => Interpreter::eval_termop(&TermOp::Mod, left, right),
+            "==" => Interpreter::eval_compop(&CompOp::Eq, left, right),
+            "!=" => Interpreter::eval_compop(&CompOp::Neq, left, right),
+            "<" => Interpreter::eval_compop(&CompOp::Lt, left, right),
+            ">" => Interpreter::eval_compop(&CompOp::Gt, left, right),
+            "<=" => Interpreter::eval_compop(&CompOp::Lte, left, right),
+            ">=" => Interpreter::eval_compop(&CompOp::Gte, left, right),
+            _ => Err(RuntimeError::new(format!("Unknown operator for s_op: {}", symbol))),
+        },
+        obj => Err(RuntimeError::new(format!("Invalid operator for s_op: {:?}", obj))),
+    }
+}
+
+fn s_string_to_chars(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    if args.len() != 1 {
+        return Err(RuntimeError::new("Must supply only one argument to s_string_to_chars"));
+    }
+    Ok(match args.first().unwrap() {
+        Object::String(string) => Object::List(string.chars().map(|c| Object::String(c.to_string())).collect()),
+        obj => return Err(RuntimeError::new(format!("Invalid argument for s_string_to_chars: {:?}", obj))),
+    })
+}
+
+fn s_chars_to_string(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    if args.len() != 1 {
+        return Err(RuntimeError::new("Must supply only one argument to s_chars_to_string"));
+    }
+    Ok(match args.first().unwrap() {
+        Object::List(list) => Object::String(list.iter().map(|obj| match obj {
+            Object::String(string) if string.chars().count() == 1 => Ok(string.as_str()),
+            obj => Err(RuntimeError::new(format!("Expected a single character in s_chars_to_string, got {:?}", obj))),
+        }).collect::<Result<_, _>>()?),
+        obj => return Err(RuntimeError::new(format!("Invalid argument for s_chars_to_string: {:?}", obj))),
+    })
+}
+
+/// every character from start to end inclusive, by Unicode scalar value
+fn s_char_range(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let bound = |obj: &Object| match obj {
+        Object::String(string) if string.chars().count() == 1 => Ok(string.chars().next().unwrap()),
+        obj => Err(RuntimeError::new(format!("s_char_range expects single-character strings, got {:?}", obj))),
+    };
+    Ok(match args.as_slice() {
+        [start, end] => Object::List((bound(start)?..=bound(end)?).map(|c| Object::String(c.to_string())).collect()),
+        _ => return Err(RuntimeError::new("Must supply exactly two arguments to s_char_range")),
+    })
+}
+
+/// n consecutive integers counting up from start, or from 0 when only n is given
+fn s_iota(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (start, n) = match args.as_slice() {
+        [Object::Integer(n)] => (0, *n),
+        [Object::Integer(start), Object::Integer(n)] => (*start, *n),
+        _ => return Err(RuntimeError::new(format!("s_iota expects a count, or a start and a count, got {:?}", args))),
+    };
+    if n < 0 {
+        return Err(RuntimeError::new(format!("s_iota got a negative count {}", n)));
+    }
+    Ok(Object::List((0..n).map(|i| start.checked_add(i).map(Object::Integer)
+        .ok_or_else(|| RuntimeError::new(format!("s_iota overflowed counting {} from {}", n, start))))
+        .collect::<Result<_, _>>()?))
+}
+
+fn s_counts(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    if args.len() != 1 {
+        return Err(RuntimeError::new("Must supply only one argument to s_counts"));
     }
-    Object::Null
+    Ok(match args.first().unwrap() {
+        Object::List(list) => {
+            let mut map = HashMap::new();
+            for obj in list {
+                let count = map.entry(stringify(obj)).or_insert(Object::Integer(0));
+                if let Object::Integer(num) = count {
+                    *num += 1;
+                }
+            }
+            Object::Map(map)
+        }
+        obj => return Err(RuntimeError::new(format!("Invalid argument for s_counts: {:?}", obj))),
+    })
 }
 
-fn s_to_bool(args: Vec<Object>) -> Object {
+fn s_normalize_whitespace(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
     if args.len() != 1 {
-        panic!("Must supply only one argument to s_to_bool");
+        return Err(RuntimeError::new("Must supply only one argument to s_normalize_whitespace"));
+    }
+    Ok(match args.first().unwrap() {
+        Object::String(string) => Object::String(string.split_whitespace().collect::<Vec<_>>().join(" ")),
+        obj => return Err(RuntimeError::new(format!("Invalid argument for s_normalize_whitespace: {:?}", obj))),
+    })
+}
+
+/// strips any of the characters in the second string from both ends of the first
+fn s_trim_chars(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (string, chars) = string_and_chars(&args, "s_trim_chars")?;
+    Ok(Object::String(string.trim_matches(chars.as_slice()).to_string()))
+}
+
+fn s_trim_start(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (string, chars) = string_and_chars(&args, "s_trim_start")?;
+    Ok(Object::String(string.trim_start_matches(chars.as_slice()).to_string()))
+}
+
+fn s_trim_end(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (string, chars) = string_and_chars(&args, "s_trim_end")?;
+    Ok(Object::String(string.trim_end_matches(chars.as_slice()).to_string()))
+}
+
+fn string_and_chars<'a>(args: &'a [Object], name: &str) -> Result<(&'a str, Vec<char>), RuntimeError> {
+    match args {
+        [Object::String(string), Object::String(chars)] => Ok((string, chars.chars().collect())),
+        _ => Err(RuntimeError::new(format!("{} expects a string and a string of characters to trim, got {:?}", name, args))),
+    }
+}
+
+/// fills the start of the string up to width characters; longer strings are left as they are
+fn s_pad_left(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (string, padding) = string_and_padding(&args, "s_pad_left")?;
+    Ok(Object::String(padding + string))
+}
+
+fn s_pad_right(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (string, padding) = string_and_padding(&args, "s_pad_right")?;
+    Ok(Object::String(string.to_string() + &padding))
+}
+
+/// the string plus the run of fill characters needed to bring it up to the width
+fn string_and_padding<'a>(args: &'a [Object], name: &str) -> Result<(&'a str, String), RuntimeError> {
+    match args {
+        [Object::String(string), Object::Integer(width), Object::String(fill)] if fill.chars().count() == 1 => {
+            let missing = usize::try_from(*width).unwrap_or(0).saturating_sub(string.chars().count());
+            Ok((string, fill.repeat(missing)))
+        }
+        _ => Err(RuntimeError::new(format!("{} expects a string, a width and a single fill character, got {:?}", name, args))),
+    }
+}
+
+/// whether any string in the list starts with the prefix
+fn s_any_starts_with(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (strings, prefix) = strings_and_prefix(&args, "s_any_starts_with")?;
+    Ok(Object::Boolean(strings.iter().any(|string| string.starts_with(prefix))))
+}
+
+/// the strings in the list that start with the prefix, in list order
+fn s_filter_prefix(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (strings, prefix) = strings_and_prefix(&args, "s_filter_prefix")?;
+    Ok(Object::List(strings.into_iter()
+        .filter(|string| string.starts_with(prefix))
+        .map(|string| Object::String(string.to_string()))
+        .collect()))
+}
+
+fn strings_and_prefix<'a>(args: &'a [Object], name: &str) -> Result<(Vec<&'a str>, &'a str), RuntimeError> {
+    match args {
+        [Object::List(list), Object::String(prefix)] => Ok((list.iter().map(|obj| match obj {
+            Object::String(string) => Ok(string.as_str()),
+            obj => Err(RuntimeError::new(format!("{} expects a list of strings, got {:?}", name, obj))),
+        }).collect::<Result<_, _>>()?, prefix)),
+        _ => Err(RuntimeError::new(format!("{} expects a list of strings and a prefix, got {:?}", name, args))),
+    }
+}
+
+fn s_args(interpreter: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::new("s_args takes no arguments"));
+    }
+    Ok(Object::List(interpreter.script_args().iter().map(|arg| Object::String(arg.clone())).collect()))
+}
+
+/// the operating system, such as "linux", "macos" or "windows"
+fn s_os(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::new("s_os takes no arguments"));
+    }
+    Ok(Object::String(String::from(env::consts::OS)))
+}
+
+/// the CPU architecture, such as "x86_64" or "aarch64"
+fn s_arch(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::new("s_arch takes no arguments"));
+    }
+    Ok(Object::String(String::from(env::consts::ARCH)))
+}
+
+/// whether start <= x < end
+fn s_in_range(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::Integer(x), Object::Integer(start), Object::Integer(end)] => Object::Boolean(start <= x && x < end),
+        _ => return Err(RuntimeError::new(format!("s_in_range expects three integers, got {:?}", args))),
+    })
+}
+
+/// [x limited to lo..=hi, whether it had to be changed]. mixing in a float clamps as floats
+fn s_clamp_report(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let float = |obj: &Object| match obj {
+        Object::Integer(num) => Some(*num as f64),
+        Object::Float(num) => Some(*num),
+        _ => None,
+    };
+    let clamped = match args.as_slice() {
+        [Object::Integer(_), Object::Integer(lo), Object::Integer(hi)] if lo > hi => {
+            return Err(RuntimeError::new(format!("s_clamp_report got an empty range {}..={}", lo, hi)));
+        }
+        [Object::Integer(x), Object::Integer(lo), Object::Integer(hi)] => Object::Integer(*x.clamp(lo, hi)),
+        [x, lo, hi] => match (float(x), float(lo), float(hi)) {
+            (Some(x), Some(lo), Some(hi)) if lo <= hi => Object::Float(x.clamp(lo, hi)),
+            (Some(_), Some(lo), Some(hi)) => {
+                return Err(RuntimeError::new(format!("s_clamp_report got an empty range {}..={}", lo, hi)));
+            }
+            _ => return Err(RuntimeError::new(format!("s_clamp_report expects three numbers, got {:?}", args))),
+        },
+        _ => return Err(RuntimeError::new(format!("s_clamp_report expects three numbers, got {:?}", args))),
+    };
+    let was_clamped = float(&clamped) != float(&args[0]);
+    Ok(Object::List(vec![clamped, Object::Boolean(was_clamped)]))
+}
+
+/// average of a non-empty list of numbers, integers or floats, as a float
+fn s_mean(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let nums = nonempty_numbers(&args, "s_mean")?;
+    Ok(Object::Float(nums.iter().sum::<f64>() / nums.len() as f64))
+}
+
+/// middle value of a numeric list, or the average of the two middle values as a float
+fn s_median(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let mut values = numbers_with_values(&args, "s_median")?;
+    values.sort_by(|(left, _), (right, _)| left.total_cmp(right));
+    let mid = values.len() / 2;
+    Ok(match values.len() % 2 {
+        0 => Object::Float((values[mid - 1].0 + values[mid].0) / 2.0),
+        _ => values[mid].1.clone(),
+    })
+}
+
+/// most frequent value of a numeric list, preferring the first seen on ties. 1 and 1.0 count as the same value
+fn s_mode(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let values = numbers_with_values(&args, "s_mode")?;
+    let mut counts: HashMap<u64, usize> = HashMap::new();
+    for (num, _) in &values {
+        *counts.entry(num.to_bits()).or_insert(0) += 1;
+    }
+    let max = *counts.values().max().unwrap();
+    Ok(values.iter().find(|(num, _)| counts[&num.to_bits()] == max).unwrap().1.clone())
+}
+
+/// index of the smallest number in the list, the first one on ties
+fn s_argmin(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let nums = nonempty_numbers(&args, "s_argmin")?;
+    Ok(Object::Integer(extreme_index(&nums, |num, best| num < best) as i32))
+}
+
+/// index of the largest number in the list, the first one on ties
+fn s_argmax(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let nums = nonempty_numbers(&args, "s_argmax")?;
+    Ok(Object::Integer(extreme_index(&nums, |num, best| num > best) as i32))
+}
+
+/// the first index whose number beats every earlier one
+fn extreme_index(nums: &[f64], beats: impl Fn(f64, f64) -> bool) -> usize {
+    (1..nums.len()).fold(0, |best, i| if beats(nums[i], nums[best]) { i } else { best })
+}
+
+/// element (or character) of a list or string at an index, falling back to a default when out of range
+fn s_get_or(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::List(list), Object::Integer(index), default] => {
+            usize::try_from(*index).ok()
+                .and_then(|index| list.get(index))
+                .unwrap_or(default)
+                .clone()
+        }
+        [Object::String(string), Object::Integer(index), default] => {
+            usize::try_from(*index).ok()
+                .and_then(|index| string.chars().nth(index))
+                .map(|c| Object::String(c.to_string()))
+                .unwrap_or_else(|| default.clone())
+        }
+        _ => {
+            return Err(RuntimeError::new(format!("s_get_or expects a list or string, an integer index, and a default, got {:?}", args)));
+        }
+    })
+}
+
+/// running totals: each element is the sum of itself and everything before it
+fn s_scan(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::List(list)] => {
+            // added the way + adds, so integers stay integers until a float comes along
+            let mut total = Object::Integer(0);
+            Object::List(list.iter().map(|obj| match obj {
+                Object::Integer(_) | Object::Float(_) => {
+                    total = Interpreter::eval_exprop(&ExprOp::Add, total.clone(), obj.clone())?;
+                    Ok(total.clone())
+                }
+                obj => Err(RuntimeError::new(format!("s_scan expects a list of numbers, got {:?}", obj))),
+            }).collect::<Result<_, _>>()?)
+        }
+        _ => return Err(RuntimeError::new(format!("s_scan expects a single list argument, got {:?}", args))),
+    })
+}
+
+fn s_gcd(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::Integer(a), Object::Integer(b)] => {
+            let gcd = gcd(*a as i64, *b as i64);
+            Object::Integer(i32::try_from(gcd)
+                .map_err(|_| RuntimeError::new(format!("gcd({}, {}) does not fit in an integer", a, b)))?)
+        }
+        _ => return Err(RuntimeError::new(format!("s_gcd expects two integers, got {:?}", args))),
+    })
+}
+
+fn s_lcm(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::Integer(a), Object::Integer(b)] => {
+            let (a, b) = (*a as i64, *b as i64);
+            let lcm = match gcd(a, b) {
+                0 => 0,
+                gcd => (a / gcd * b).abs(),
+            };
+            Object::Integer(i32::try_from(lcm)
+                .map_err(|_| RuntimeError::new(format!("lcm({}, {}) does not fit in an integer", a, b)))?)
+        }
+        _ => return Err(RuntimeError::new(format!("s_lcm expects two integers, got {:?}", args))),
+    })
+}
+
+/// non-negative greatest common divisor by Euclid's algorithm, gcd(0, 0) = 0
+fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        let rem = a % b;
+        a = b;
+        b = rem;
+    }
+    a
+}
+
+/// unwraps the single non-empty list of numbers, integers or floats, passed to a statistics builtin
+fn nonempty_numbers(args: &[Object], name: &str) -> Result<Vec<f64>, RuntimeError> {
+    match args {
+        [Object::List(list)] if list.is_empty() => Err(RuntimeError::new(format!("Cannot call {} on an empty list", name))),
+        [Object::List(list)] => list.iter().map(|obj| match obj {
+            Object::Integer(num) => Ok(*num as f64),
+            Object::Float(num) => Ok(*num),
+            obj => Err(RuntimeError::new(format!("{} expects a list of numbers, got {:?}", name, obj))),
+        }).collect(),
+        _ => Err(RuntimeError::new(format!("{} expects a single list argument, got {:?}", name, args))),
+    }
+}
+
+/// like nonempty_numbers, but each number is paired with the list element it came from
+fn numbers_with_values<'a>(args: &'a [Object], name: &str) -> Result<Vec<(f64, &'a Object)>, RuntimeError> {
+    let nums = nonempty_numbers(args, name)?;
+    match args {
+        [Object::List(list)] => Ok(nums.into_iter().zip(list).collect()),
+        _ => unreachable!("nonempty_numbers only accepts a single list"),
+    }
+}
+
+fn s_deep_equals(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [left, right] => Object::Boolean(deep_equals(left, right)),
+        _ => return Err(RuntimeError::new("Must supply exactly two arguments to s_deep_equals")),
+    })
+}
+
+/// formats an integer with its digits grouped in threes, separated by "," or the given separator
+fn s_group_digits(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (num, separator) = match args.as_slice() {
+        [Object::Integer(num)] => (*num, ","),
+        [Object::Integer(num), Object::String(separator)] => (*num, separator.as_str()),
+        _ => {
+            return Err(RuntimeError::new(format!("s_group_digits expects an integer and an optional separator string, got {:?}", args)));
+        }
+    };
+    let digits = (num as i64).abs().to_string();
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push_str(separator);
+        }
+        grouped.push(c);
+    }
+    if num < 0 {
+        grouped.insert(0, '-');
+    }
+    Ok(Object::String(grouped))
+}
+
+/// descends through nested maps (by string key) and lists (by index), returning null if any step is missing
+fn s_get_path(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [value, Object::List(path)] => {
+            let mut current = value;
+            for step in path {
+                let next = match (current, step) {
+                    (Object::Map(map), Object::String(key)) => map.get(key),
+                    (Object::List(list), Object::Integer(index)) => usize::try_from(*index).ok().and_then(|index| list.get(index)),
+                    _ => None,
+                };
+                match next {
+                    Some(obj) => current = obj,
+                    None => return Ok(Object::Null),
+                }
+            }
+            current.clone()
+        }
+        _ => return Err(RuntimeError::new(format!("s_get_path expects a value and a path list, got {:?}", args))),
+    })
+}
+
+/// returns a copy of the value with the location at the path replaced; missing map keys are created
+fn s_set_path(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    match args.as_slice() {
+        [value, Object::List(path), new] => set_path(value, path, new),
+        _ => Err(RuntimeError::new(format!("s_set_path expects a value, a path list, and a new value, got {:?}", args))),
+    }
+}
+
+fn set_path(value: &Object, path: &[Object], new: &Object) -> Result<Object, RuntimeError> {
+    let (step, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return Ok(new.clone()),
+    };
+    Ok(match (value, step) {
+        (Object::Map(map), Object::String(key)) => {
+            let mut map = map.clone();
+            let child = map.get(key).cloned().unwrap_or_else(|| Object::Map(HashMap::new()));
+            map.insert(key.clone(), set_path(&child, rest, new)?);
+            Object::Map(map)
+        }
+        (Object::List(list), Object::Integer(index)) => {
+            let mut list = list.clone();
+            let slot = usize::try_from(*index).ok()
+                .and_then(|index| list.get_mut(index))
+                .ok_or_else(|| RuntimeError::new(format!("Index {} out of range in s_set_path", index)))?;
+            *slot = set_path(slot, rest, new)?;
+            Object::List(list)
+        }
+        (value, step) => return Err(RuntimeError::new(format!("Cannot follow path step {:?} into {:?}", step, value))),
+    })
+}
+
+/// a single-level map from dotted paths like "a.b.0" to each leaf of a nested map
+fn s_flatten_map(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::Map(map)] => {
+            let mut flat = HashMap::new();
+            for (key, value) in map {
+                flatten_into(&mut flat, key.clone(), value);
+            }
+            Object::Map(flat)
+        }
+        _ => return Err(RuntimeError::new(format!("s_flatten_map expects a single map, got {:?}", args))),
+    })
+}
+
+/// empty maps and lists are kept as leaves so they don't disappear
+fn flatten_into(flat: &mut HashMap<String, Object>, path: String, value: &Object) {
+    match value {
+        Object::Map(map) if !map.is_empty() => {
+            for (key, child) in map {
+                flatten_into(flat, format!("{}.{}", path, key), child);
+            }
+        }
+        Object::List(list) if !list.is_empty() => {
+            for (i, child) in list.iter().enumerate() {
+                flatten_into(flat, format!("{}.{}", path, i), child);
+            }
+        }
+        leaf => {
+            flat.insert(path, leaf.clone());
+        }
+    }
+}
+
+/// a copy of base with override's keys laid over it. maps found under the same key in both are
+/// merged the same way; anything else from override replaces what base had
+fn s_merge(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::Map(base), Object::Map(overrides)] => Object::Map(merge_maps(base, overrides)),
+        _ => return Err(RuntimeError::new(format!("s_merge expects two maps, got {:?}", args))),
+    })
+}
+
+fn merge_maps(base: &HashMap<String, Object>, overrides: &HashMap<String, Object>) -> HashMap<String, Object> {
+    let mut merged = base.clone();
+    for (key, value) in overrides {
+        let value = match (merged.get(key), value) {
+            (Some(Object::Map(inner_base)), Object::Map(inner_overrides)) => Object::Map(merge_maps(inner_base, inner_overrides)),
+            _ => value.clone(),
+        };
+        merged.insert(key.clone(), value);
+    }
+    merged
+}
+
+const DATE_PARTS: [&str; 6] = ["year", "month", "day", "hour", "minute", "second"];
+
+/// splits a unix timestamp into a map of UTC year/month/day/hour/minute/second
+fn s_timestamp_to_parts(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let secs = match args.as_slice() {
+        [Object::Integer(secs)] => *secs as i64,
+        _ => return Err(RuntimeError::new(format!("s_timestamp_to_parts expects a single integer, got {:?}", args))),
+    };
+    let (days, time) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    // days since the epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    let values = [year, month, day, time / 3600, time % 3600 / 60, time % 60];
+    Ok(Object::Map(DATE_PARTS.iter()
+        .zip(values.iter())
+        .map(|(name, value)| (name.to_string(), Object::Integer(*value as i32)))
+        .collect()))
+}
+
+/// the inverse of timestamp_to_parts
+fn s_parts_to_timestamp(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let map = match args.as_slice() {
+        [Object::Map(map)] => map,
+        _ => return Err(RuntimeError::new(format!("s_parts_to_timestamp expects a single map, got {:?}", args))),
+    };
+    let parts = DATE_PARTS.iter().map(|name| match map.get(*name) {
+        Some(Object::Integer(value)) => Ok(*value as i64),
+        obj => Err(RuntimeError::new(format!("s_parts_to_timestamp expects an integer {:?} entry, got {:?}", name, obj))),
+    }).collect::<Result<Vec<_>, _>>()?;
+    let (year, month, day) = (parts[0], parts[1], parts[2]);
+    let leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if leap_year => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    let ranges = [(1, 12), (1, days_in_month), (0, 23), (0, 59), (0, 59)];
+    for ((name, value), (low, high)) in DATE_PARTS[1..].iter().zip(&parts[1..]).zip(ranges.iter()) {
+        if !(low..=high).contains(&value) {
+            return Err(RuntimeError::new(format!("s_parts_to_timestamp expects a {} from {} to {}, got {}", name, low, high, value)));
+        }
+    }
+    // civil date to days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    let secs = days * 86400 + parts[3] * 3600 + parts[4] * 60 + parts[5];
+    Ok(Object::Integer(i32::try_from(secs)
+        .map_err(|_| RuntimeError::new(format!("Timestamp {} does not fit in an integer", secs)))?))
+}
+
+/// splits a list into sublists of the given size, the last of which may be shorter
+fn s_chunk(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::List(_), Object::Integer(size)] if *size <= 0 => {
+            return Err(RuntimeError::new(format!("Chunk size must be positive, got {}", size)));
+        }
+        [Object::List(list), Object::Integer(size)] => {
+            Object::List(list.chunks(*size as usize).map(|chunk| Object::List(chunk.to_vec())).collect())
+        }
+        _ => return Err(RuntimeError::new(format!("s_chunk expects a list and an integer size, got {:?}", args))),
+    })
+}
+
+/// rotates a copy of the list left by n positions (right for negative n), wrapping n by the length
+fn s_rotate(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::List(list), Object::Integer(_)] if list.is_empty() => Object::List(Vec::new()),
+        [Object::List(list), Object::Integer(n)] => {
+            let mut list = list.clone();
+            let n = (*n as i64).rem_euclid(list.len() as i64) as usize;
+            list.rotate_left(n);
+            Object::List(list)
+        }
+        _ => return Err(RuntimeError::new(format!("s_rotate expects a list and an integer, got {:?}", args))),
+    })
+}
+
+/// a copy of the list with the elements at i and j exchanged
+fn s_swap(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::List(list), Object::Integer(i), Object::Integer(j)] => {
+            let in_range = |index: i32| usize::try_from(index).ok()
+                .filter(|index| *index < list.len())
+                .ok_or_else(|| RuntimeError::new(format!("Index {} out of range in s_swap", index)));
+            let (i, j) = (in_range(*i)?, in_range(*j)?);
+            let mut list = list.clone();
+            list.swap(i, j);
+            Object::List(list)
+        }
+        _ => return Err(RuntimeError::new(format!("s_swap expects a list and two integer indices, got {:?}", args))),
+    })
+}
+
+/// combinations and permutations refuse to build more results than this
+const MAX_ARRANGEMENTS: u64 = 100_000;
+
+/// every k-element sublist, keeping the elements in list order
+fn s_combinations(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (list, k) = match args.as_slice() {
+        [Object::List(list), Object::Integer(k)] if *k >= 0 => (list, *k as usize),
+        _ => return Err(RuntimeError::new(format!("s_combinations expects a list and a non-negative size, got {:?}", args))),
+    };
+    if k > list.len() {
+        return Ok(Object::List(Vec::new()));
+    }
+    // n choose k, built up so every step stays a whole number
+    let count = (0..k as u64).try_fold(1u64, |count, i| count.checked_mul(list.len() as u64 - i).map(|product| product / (i + 1)));
+    check_arrangements(count, "s_combinations")?;
+    let mut combinations = Vec::new();
+    let mut indices = (0..k).collect::<Vec<_>>();
+    loop {
+        combinations.push(Object::List(indices.iter().map(|&i| list[i].clone()).collect()));
+        // advance the rightmost index that still has room, then reset the ones after it
+        match (0..k).rev().find(|&i| indices[i] < list.len() - k + i) {
+            Some(i) => {
+                indices[i] += 1;
+                for j in i + 1..k {
+                    indices[j] = indices[j - 1] + 1;
+                }
+            }
+            None => break,
+        }
+    }
+    Ok(Object::List(combinations))
+}
+
+/// every ordering of the list, starting with the list as given
+fn s_permutations(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let list = match args.as_slice() {
+        [Object::List(list)] => list,
+        _ => return Err(RuntimeError::new(format!("s_permutations expects a single list, got {:?}", args))),
+    };
+    check_arrangements((1..=list.len() as u64).try_fold(1u64, u64::checked_mul), "s_permutations")?;
+    let mut permutations = Vec::new();
+    permute(&mut list.clone(), 0, &mut permutations);
+    Ok(Object::List(permutations))
+}
+
+/// fills position start with each remaining element in turn, putting the order back afterwards
+fn permute(list: &mut Vec<Object>, start: usize, permutations: &mut Vec<Object>) {
+    if start + 1 >= list.len() {
+        permutations.push(Object::List(list.clone()));
+        return;
+    }
+    for i in start..list.len() {
+        list[start..=i].rotate_right(1);
+        permute(list, start + 1, permutations);
+        list[start..=i].rotate_left(1);
+    }
+}
+
+fn check_arrangements(count: Option<u64>, name: &str) -> Result<(), RuntimeError> {
+    match count {
+        Some(count) if count <= MAX_ARRANGEMENTS => Ok(()),
+        _ => Err(RuntimeError::new(format!("{} would produce more than {} results", name, MAX_ARRANGEMENTS))),
+    }
+}
+
+/// truncates or pads a copy of the list with fill so it has exactly n elements
+fn s_resize(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::List(_), Object::Integer(n), _] if *n < 0 => {
+            return Err(RuntimeError::new(format!("Cannot resize a list to {} elements", n)));
+        }
+        [Object::List(list), Object::Integer(n), fill] => {
+            let mut list = list.clone();
+            list.resize(*n as usize, fill.clone());
+            Object::List(list)
+        }
+        _ => {
+            return Err(RuntimeError::new(format!("s_resize expects a list, an integer length and a fill value, got {:?}", args)));
+        }
+    })
+}
+
+/// pairs elements by index up to the longer list's length, filling the shorter side's gaps
+fn s_zip_longest(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::List(a), Object::List(b), fill] => Object::List((0..a.len().max(b.len()))
+            .map(|i| Object::List(vec![
+                a.get(i).unwrap_or(fill).clone(),
+                b.get(i).unwrap_or(fill).clone(),
+            ]))
+            .collect()),
+        _ => return Err(RuntimeError::new(format!("s_zip_longest expects two lists and a fill value, got {:?}", args))),
+    })
+}
+
+/// pairs keys[i], stringified, with values[i]; a repeated key keeps its last value
+fn s_zip_to_map(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::List(keys), Object::List(values)] if keys.len() != values.len() => {
+            return Err(RuntimeError::new(format!("s_zip_to_map got {} keys but {} values", keys.len(), values.len())))
+        }
+        [Object::List(keys), Object::List(values)] => {
+            Object::Map(keys.iter().map(stringify).zip(values.iter().cloned()).collect())
+        }
+        _ => return Err(RuntimeError::new(format!("s_zip_to_map expects a list of keys and a list of values, got {:?}", args))),
+    })
+}
+
+/// elements of a that are not in b, in a's order
+fn s_difference(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (a, b) = two_lists(&args, "s_difference")?;
+    Ok(Object::List(a.iter().filter(|obj| !b.iter().any(|other| deep_equals(obj, other))).cloned().collect()))
+}
+
+/// elements of a that are also in b, in a's order
+fn s_intersection(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (a, b) = two_lists(&args, "s_intersection")?;
+    Ok(Object::List(a.iter().filter(|obj| b.iter().any(|other| deep_equals(obj, other))).cloned().collect()))
+}
+
+/// a followed by the elements of b that aren't already included
+fn s_union(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (a, b) = two_lists(&args, "s_union")?;
+    let mut union = a.clone();
+    for obj in b {
+        if !union.iter().any(|other| deep_equals(obj, other)) {
+            union.push(obj.clone());
+        }
+    }
+    Ok(Object::List(union))
+}
+
+/// whether a list of numbers or of strings never decreases. with a comparator, whether it
+/// returns true for every adjacent pair instead
+fn s_is_sorted(interpreter: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (list, comparator) = match args.as_slice() {
+        [Object::List(list)] => (list, None),
+        [Object::List(list), comparator] => (list, Some(comparator)),
+        _ => return Err(RuntimeError::new(format!("s_is_sorted expects a list and an optional comparator, got {:?}", args))),
+    };
+    for pair in list.windows(2) {
+        let in_order = match (pair, comparator) {
+            (_, Some(comparator)) => to_bool(&interpreter.call_object(comparator.clone(), pair.to_vec())?)?,
+            ([Object::String(a), Object::String(b)], None) => a <= b,
+            ([a @ (Object::Integer(_) | Object::Float(_)), b @ (Object::Integer(_) | Object::Float(_))], None) => {
+                Interpreter::eval_compop(&CompOp::Lte, a.clone(), b.clone())? == Object::Boolean(true)
+            }
+            (pair, None) => {
+                return Err(RuntimeError::new(format!("s_is_sorted expects only numbers or only strings, got {:?}", pair)));
+            }
+        };
+        if !in_order {
+            return Ok(Object::Boolean(false));
+        }
+    }
+    Ok(Object::Boolean(true))
+}
+
+fn two_lists<'a>(args: &'a [Object], name: &str) -> Result<(&'a Vec<Object>, &'a Vec<Object>), RuntimeError> {
+    match args {
+        [Object::List(a), Object::List(b)] => Ok((a, b)),
+        _ => Err(RuntimeError::new(format!("{} expects two lists, got {:?}", name, args))),
+    }
+}
+
+fn s_size_of(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [value] => Object::Integer(size_of(value) as i32),
+        _ => return Err(RuntimeError::new("Must supply only one argument to s_size_of")),
+    })
+}
+
+/// best-effort estimate of the bytes a value holds: string contents, element sizes, and so on
+fn size_of(value: &Object) -> usize {
+    match value {
+        Object::String(string) => string.len(),
+        Object::Integer(_) => mem::size_of::<i32>(),
+        Object::Float(_) => mem::size_of::<f64>(),
+        Object::Boolean(_) => mem::size_of::<bool>(),
+        Object::List(list) => list.iter().map(size_of).sum(),
+        Object::Map(map) => map.iter().map(|(key, obj)| key.len() + size_of(obj)).sum(),
+        Object::RustFunction(_) | Object::Function(_, _, _) | Object::Memoized(_, _) => mem::size_of::<Object>(),
+        Object::Null => 0,
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn s_to_base64(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let bytes = single_string(&args, "s_to_base64")?.as_bytes();
+    let mut encoded = String::new();
+    for group in bytes.chunks(3) {
+        let n = group.iter().enumerate().fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= group.len() {
+                encoded.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    Ok(Object::String(encoded))
+}
+
+fn s_from_base64(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let string = single_string(&args, "s_from_base64")?;
+    let trimmed = string.trim_end_matches('=');
+    if !string.len().is_multiple_of(4) || string.len() - trimmed.len() > 2 {
+        return Err(RuntimeError::new(format!("Invalid base64 string: {:?}", string)));
+    }
+    let mut bytes = Vec::new();
+    for group in trimmed.as_bytes().chunks(4) {
+        let n = group.iter().enumerate().try_fold(0u32, |n, (i, c)| {
+            let value = BASE64_ALPHABET.iter().position(|b| b == c)
+                .ok_or_else(|| RuntimeError::new(format!("Invalid base64 character {:?} in {:?}", *c as char, string)))?;
+            Ok::<_, RuntimeError>(n | (value as u32) << (18 - 6 * i))
+        })?;
+        for i in 0..group.len() - 1 {
+            bytes.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(Object::String(String::from_utf8(bytes)
+        .map_err(|_| RuntimeError::new(format!("Decoded base64 is not valid UTF-8: {:?}", string)))?))
+}
+
+fn s_to_hex_string(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let bytes = single_string(&args, "s_to_hex_string")?.as_bytes();
+    Ok(Object::String(bytes.iter().map(|byte| format!("{:02x}", byte)).collect()))
+}
+
+fn s_from_hex_string(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let string = single_string(&args, "s_from_hex_string")?;
+    if !string.len().is_multiple_of(2) || !string.is_ascii() {
+        return Err(RuntimeError::new(format!("Invalid hex string: {:?}", string)));
+    }
+    let bytes = (0..string.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&string[i..i + 2], 16).map_err(|_| RuntimeError::new(format!("Invalid hex string: {:?}", string))))
+        .collect::<Result<_, _>>()?;
+    Ok(Object::String(String::from_utf8(bytes)
+        .map_err(|_| RuntimeError::new(format!("Decoded hex is not valid UTF-8: {:?}", string)))?))
+}
+
+fn s_hash(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [value] => {
+            let hash = hash(value)?;
+            Object::Integer((hash ^ hash >> 32) as i32)
+        }
+        _ => return Err(RuntimeError::new("Must supply only one argument to s_hash")),
+    })
+}
+
+/// FNV-1a over a tagged encoding of the value, so it is stable across runs and platforms
+fn hash(value: &Object) -> Result<u64, RuntimeError> {
+    fn fnv(tag: u8, bytes: &[u8]) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in [tag].iter().chain(bytes) {
+            hash = (hash ^ *byte as u64).wrapping_mul(0x100000001b3);
+        }
+        hash
+    }
+    Ok(match value {
+        Object::String(string) => fnv(b's', string.as_bytes()),
+        Object::Integer(num) => fnv(b'i', &num.to_le_bytes()),
+        Object::Float(num) => fnv(b'f', &num.to_bits().to_le_bytes()),
+        Object::Boolean(val) => fnv(b'b', &[*val as u8]),
+        Object::Null => fnv(b'n', &[]),
+        Object::List(list) => {
+            let hashes = list.iter().map(hash).collect::<Result<Vec<_>, _>>()?;
+            fnv(b'l', &hashes.iter().flat_map(|hash| hash.to_le_bytes().to_vec()).collect::<Vec<_>>())
+        }
+        Object::Map(map) => { // sum the entry hashes so insertion order doesn't matter
+            let entries = map.iter().try_fold(0u64, |sum, (key, obj)| {
+                let entry = [fnv(b's', key.as_bytes()).to_le_bytes(), hash(obj)?.to_le_bytes()].concat();
+                Ok::<_, RuntimeError>(sum.wrapping_add(fnv(b'e', &entry)))
+            })?;
+            fnv(b'm', &entries.to_le_bytes())
+        }
+        obj => return Err(RuntimeError::new(format!("Cannot hash {:?}", obj))),
+    })
+}
+
+/// replaces each {name} in the template with the value of the variable name in the calling scope
+fn s_interpolate(interpreter: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let template = single_string(&args, "s_interpolate")?;
+    let mut result = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break, // an unclosed brace is left as is
+        };
+        let name = &rest[start + 1..end];
+        let value = interpreter.lookup(name)
+            .ok_or_else(|| RuntimeError::new(format!("Variable {} referenced in s_interpolate is not defined", name)))?;
+        result.push_str(&rest[..start]);
+        result.push_str(&stringify(&value));
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    Ok(Object::String(result))
+}
+
+/// a map's keys in lexicographic order, since map iteration order is arbitrary
+fn s_sorted_keys(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::Map(map)] => {
+            let mut keys = map.keys().cloned().collect::<Vec<_>>();
+            keys.sort();
+            Object::List(keys.into_iter().map(Object::String).collect())
+        }
+        _ => return Err(RuntimeError::new(format!("s_sorted_keys expects a single map, got {:?}", args))),
+    })
+}
+
+/// the first argument that isn't null, or null if they all are
+fn s_coalesce(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(args.into_iter().find(|obj| *obj != Object::Null).unwrap_or(Object::Null))
+}
+
+/// wraps a function so repeated calls with the same arguments reuse the first result
+fn s_memoize(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [func @ Object::RustFunction(_)] | [func @ Object::Function(_, _, _)] | [func @ Object::Memoized(_, _)] => {
+            Object::Memoized(Box::new(func.clone()), Rc::new(RefCell::new(HashMap::new())))
+        }
+        _ => return Err(RuntimeError::new(format!("s_memoize expects a single function, got {:?}", args))),
+    })
+}
+
+/// the results of calling the function n times, passing it the call's index unless it takes no parameters
+fn s_generate(interpreter: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (n, func) = match args.as_slice() {
+        [Object::Integer(n), _] if *n < 0 => return Err(RuntimeError::new(format!("s_generate got a negative count {}", n))),
+        [Object::Integer(n), func] => (*n, func),
+        _ => return Err(RuntimeError::new(format!("s_generate expects a count and a function, got {:?}", args))),
+    };
+    let pass_index = !takes_no_params(func);
+    Ok(Object::List((0..n).map(|i| {
+        let args = if pass_index { vec![Object::Integer(i)] } else { Vec::new() };
+        interpreter.call_object(func.clone(), args)
+    }).collect::<Result<_, _>>()?))
+}
+
+/// builtins can't say how many arguments they take, so they count as taking some
+fn takes_no_params(func: &Object) -> bool {
+    match func {
+        Object::Function(params, _, _) => params.is_empty(),
+        Object::Memoized(func, _) => takes_no_params(func),
+        _ => false,
+    }
+}
+
+fn s_is_callable(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [obj] => Object::Boolean(is_callable(obj)),
+        _ => return Err(RuntimeError::new("Must supply only one argument to s_is_callable")),
+    })
+}
+
+fn is_callable(obj: &Object) -> bool {
+    matches!(obj, Object::RustFunction(_) | Object::Function(_, _, _) | Object::Memoized(_, _))
+}
+
+fn s_type(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [obj] => Object::String(type_name(obj).to_string()),
+        _ => return Err(RuntimeError::new("Must supply only one argument to s_type")),
+    })
+}
+
+/// returns the value unchanged if type(value) is the given name, so it can be used inline
+fn s_assert_type(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [obj, Object::String(expected)] => {
+            if type_name(obj) != expected {
+                return Err(RuntimeError::new(format!("Expected a value of type {}, got {} {:?}", expected, type_name(obj), obj)));
+            }
+            obj.clone()
+        }
+        _ => return Err(RuntimeError::new(format!("s_assert_type expects a value and a type name, got {:?}", args))),
+    })
+}
+
+/// calls the function with no arguments and returns the message of the error it raises,
+/// or errors itself if the call succeeds
+fn s_assert_throws(interpreter: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let func = match args.as_slice() {
+        [func] if is_callable(func) => func.clone(),
+        _ => return Err(RuntimeError::new(format!("s_assert_throws expects a single function, got {:?}", args))),
+    };
+    match interpreter.call_object(func, Vec::new()) {
+        Ok(_) => Err(RuntimeError::new("expected an error but none occurred")),
+        Err(err) => Ok(Object::String(err.message)),
+    }
+}
+
+/// how many script-defined function calls are in progress; builtins don't count
+fn s_stack_depth(interpreter: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::new("s_stack_depth takes no arguments"));
+    }
+    Ok(Object::Integer(i32::try_from(interpreter.call_depth()).unwrap_or(i32::MAX)))
+}
+
+/// how many statements, expressions and factors make up a function's body, as a rough measure of its size
+fn s_node_count(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [Object::Function(_, body, _)] => Object::Integer(body.node_count() as i32),
+        _ => return Err(RuntimeError::new(format!("s_node_count expects a function defined in the script, got {:?}", args))),
+    })
+}
+
+pub fn type_name(obj: &Object) -> &'static str {
+    match obj {
+        Object::String(_) => "string",
+        Object::Integer(_) => "integer",
+        Object::Float(_) => "float",
+        Object::Boolean(_) => "boolean",
+        Object::List(_) => "list",
+        Object::Map(_) => "map",
+        Object::RustFunction(_) | Object::Function(_, _, _) | Object::Memoized(_, _) => "function",
+        Object::Null => "null",
+    }
+}
+
+/// splits on \n or \r\n without keeping the terminators; a trailing newline adds no empty line
+fn s_lines(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let string = single_string(&args, "s_lines")?;
+    Ok(Object::List(string.lines().map(|line| Object::String(line.to_string())).collect()))
+}
+
+/// lays out a list of rows (lists of values) as left-aligned columns separated by two spaces
+fn s_format_table(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let rows = match args.as_slice() {
+        [Object::List(rows)] => rows.iter().map(|row| match row {
+            Object::List(cells) => Ok(cells.iter().map(stringify).collect::<Vec<_>>()),
+            obj => Err(RuntimeError::new(format!("s_format_table expects each row to be a list, got {:?}", obj))),
+        }).collect::<Result<Vec<_>, _>>()?,
+        _ => return Err(RuntimeError::new(format!("s_format_table expects a single list of rows, got {:?}", args))),
+    };
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths = (0..columns)
+        .map(|column| rows.iter().filter_map(|row| row.get(column)).map(|cell| cell.chars().count()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+    Ok(Object::String(rows.iter().map(|row| {
+        let line = widths.iter().enumerate()
+            .map(|(column, width)| {
+                let cell = row.get(column).map_or("", String::as_str); // ragged rows get empty cells
+                format!("{}{}", cell, " ".repeat(width - cell.chars().count()))
+            })
+            .collect::<Vec<_>>()
+            .join("  ");
+        line.trim_end().to_string()
+    }).collect::<Vec<_>>().join("\n")))
+}
+
+/// whether the pattern matches anywhere in the string
+fn s_matches(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (string, regex) = string_and_regex(&args, "s_matches")?;
+    Ok(Object::Boolean(regex.is_match(string)))
+}
+
+/// every non-overlapping match of the pattern, in order
+fn s_find_all(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (string, regex) = string_and_regex(&args, "s_find_all")?;
+    Ok(Object::List(regex.find_iter(string).map(|found| Object::String(found.as_str().to_string())).collect()))
+}
+
+/// replaces every match of the pattern; the replacement may refer to groups as $1 or ${name}
+fn s_regex_replace(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [_, _, Object::String(replacement)] => {
+            let (string, regex) = string_and_regex(&args[..2], "s_regex_replace")?;
+            Object::String(regex.replace_all(string, replacement.as_str()).into_owned())
+        }
+        _ => {
+            return Err(RuntimeError::new(format!("s_regex_replace expects a string, a pattern, and a replacement, got {:?}", args)));
+        }
+    })
+}
+
+/// the pieces of the string between matches of the pattern, which must not match empty text
+fn s_regex_split(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    let (string, regex) = string_and_regex(&args, "s_regex_split")?;
+    if regex.find_iter(string).any(|found| found.as_str().is_empty()) {
+        return Err(RuntimeError::new(format!("Pattern {:?} in s_regex_split matches empty text", regex.as_str())));
+    }
+    Ok(Object::List(regex.split(string).map(|piece| Object::String(piece.to_string())).collect()))
+}
+
+/// a read-only map of the interpreter's active settings
+fn s_settings(interpreter: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::new("s_settings takes no arguments"));
+    }
+    let mut map = HashMap::new();
+    map.insert(String::from("warnings"), Object::Boolean(interpreter.warnings_enabled()));
+    Ok(Object::Map(map))
+}
+
+/// unwraps a (string, pattern) argument pair, compiling the pattern
+fn string_and_regex<'a>(args: &'a [Object], name: &str) -> Result<(&'a str, Regex), RuntimeError> {
+    match args {
+        [Object::String(string), Object::String(pattern)] => {
+            let regex = Regex::new(pattern)
+                .map_err(|err| RuntimeError::new(format!("Invalid pattern {:?} in {}: {}", pattern, name, err)))?;
+            Ok((string, regex))
+        }
+        _ => Err(RuntimeError::new(format!("{} expects a string and a pattern, got {:?}", name, args))),
+    }
+}
+
+/// unwraps the single string argument of a builtin
+fn single_string<'a>(args: &'a [Object], name: &str) -> Result<&'a str, RuntimeError> {
+    match args {
+        [Object::String(string)] => Ok(string),
+        _ => Err(RuntimeError::new(format!("{} expects a single string, got {:?}", name, args))),
+    }
+}
+
+/// structural equality: lists and maps compare element by element, numbers compare the way == does,
+/// so 1 equals 1.0, and functions are never equal
+fn deep_equals(left: &Object, right: &Object) -> bool {
+    match (left, right) {
+        (Object::Integer(_) | Object::Float(_), Object::Integer(_) | Object::Float(_)) => {
+            matches!(Interpreter::eval_compop(&CompOp::Eq, left.clone(), right.clone()), Ok(Object::Boolean(true)))
+        }
+        (Object::List(l_list), Object::List(r_list)) => {
+            l_list.len() == r_list.len() && l_list.iter().zip(r_list).all(|(l, r)| deep_equals(l, r))
+        }
+        (Object::Map(l_map), Object::Map(r_map)) => {
+            l_map.len() == r_map.len() && l_map.iter().all(|(key, l)| r_map.get(key).is_some_and(|r| deep_equals(l, r)))
+        }
+        (Object::RustFunction(_), _) | (Object::Function(_, _, _), _) | (Object::Memoized(_, _), _) => false,
+        (left, right) => left == right,
     }
-    Object::Boolean(to_bool(args.get(0).unwrap()))
 }
 
-pub fn to_bool(arg: &Object) -> bool {
+fn s_pretty(_: &mut Interpreter, args: Vec<Object>) -> Result<Object, RuntimeError> {
+    Ok(match args.as_slice() {
+        [value] => Object::String(pretty(value, 0)),
+        _ => return Err(RuntimeError::new("Must supply only one argument to s_pretty")),
+    })
+}
+
+/// like stringify, but puts each list element and map entry on its own line, indented by two spaces per level
+fn pretty(value: &Object, depth: usize) -> String {
+    let indent = "  ".repeat(depth + 1);
+    match value {
+        Object::List(list) if !list.is_empty() => {
+            let items = list.iter().map(|obj| format!("{}{}", indent, pretty(obj, depth + 1))).collect::<Vec<_>>();
+            format!("[\n{}\n{}]", items.join(",\n"), "  ".repeat(depth))
+        }
+        Object::Map(map) if !map.is_empty() => {
+            let mut keys = map.keys().collect::<Vec<_>>();
+            keys.sort();
+            let items = keys.iter().map(|key| format!("{}{}: {}", indent, key, pretty(&map[*key], depth + 1))).collect::<Vec<_>>();
+            format!("{{\n{}\n{}}}", items.join(",\n"), "  ".repeat(depth))
+        }
+        value => stringify(value),
+    }
+}
+
+/// renders an object as plain text, used wherever a value has to become a string key
+pub fn stringify(arg: &Object) -> String {
+    arg.to_string()
+}
+
+pub fn to_bool(arg: &Object) -> Result<bool, RuntimeError> {
     match arg {
-        Object::Boolean(val) => *val,
-        Object::Integer(num) => *num != 0,
-        Object::String(string) => string.len() != 0,
-        obj => panic!("Cannot convert {:?} to boolean", obj),
+        Object::Boolean(val) => Ok(*val),
+        Object::Integer(num) => Ok(*num != 0),
+        Object::Float(num) => Ok(*num != 0.0),
+        Object::String(string) => Ok(!string.is_empty()),
+        obj => Err(RuntimeError::new(format!("Cannot convert {:?} to boolean", obj))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::interpreter::Interpreter;
+    use crate::object::Object;
+    use crate::{eval_in, run};
+
+    fn eval(source: &str) -> Object {
+        run(source).unwrap_or_else(|err| panic!("{} failed: {}", source, err))
+    }
+
+    fn error(source: &str) -> String {
+        match run(source) {
+            Ok(value) => panic!("{} should have failed, got {:?}", source, value),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    fn string(string: &str) -> Object {
+        Object::String(String::from(string))
+    }
+
+    fn map(entries: &[(&str, Object)]) -> Object {
+        Object::Map(entries.iter().map(|(key, value)| (key.to_string(), value.clone())).collect::<HashMap<_, _>>())
+    }
+
+    #[test]
+    fn stack_depth_grows_with_recursion() {
+        let source = "set f to func takes (n) { (call stack_depth) if n == 0 else call f with (n - 1) }; \
+            [call stack_depth, call f with (0), call f with (2)]";
+        assert_eq!(eval(source), Object::List(vec![Object::Integer(0), Object::Integer(1), Object::Integer(3)]));
+    }
+
+    #[test]
+    fn stack_depth_resets_after_an_error() {
+        assert_eq!(eval("call assert_throws with (func { 1 / 0 }); call stack_depth"), Object::Integer(0));
+    }
+
+    #[test]
+    fn assert_throws_returns_the_error_message() {
+        assert_eq!(eval("call assert_throws with (func { 1 / 0 })"), string("Division by zero in 1 / 0"));
+    }
+
+    #[test]
+    fn assert_throws_fails_when_nothing_is_raised() {
+        assert_eq!(error("call assert_throws with (func { 1 })"), "expected an error but none occurred");
+    }
+
+    #[test]
+    fn assert_throws_needs_a_function() {
+        assert_eq!(error("call assert_throws with (5)"), "s_assert_throws expects a single function, got [Integer(5)]");
+    }
+
+    #[test]
+    fn args_come_from_the_interpreter() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(eval_in(&mut interpreter, "call args").unwrap(), Object::List(Vec::new()));
+        interpreter.set_script_args(vec![String::from("a"), String::from("b")]);
+        assert_eq!(eval_in(&mut interpreter, "call args").unwrap(), Object::List(vec![string("a"), string("b")]));
+    }
+
+    #[test]
+    fn mean_median_and_mode_of_integers() {
+        assert_eq!(eval("call mean with ([1, 2, 3, 4])"), Object::Float(2.5));
+        assert_eq!(eval("call median with ([3, 1, 2])"), Object::Integer(2));
+        assert_eq!(eval("call median with ([1, 2])"), Object::Float(1.5));
+        assert_eq!(eval("call mode with ([1, 3, 3, 2, 1, 3])"), Object::Integer(3));
+    }
+
+    #[test]
+    fn mean_median_and_mode_accept_floats() {
+        assert_eq!(eval("call mean with ([1.5, 2])"), Object::Float(1.75));
+        assert_eq!(eval("call median with ([2.5, 0.5, 1])"), Object::Integer(1));
+        assert_eq!(eval("call median with ([0.5, 1.5, 2, 4])"), Object::Float(1.75));
+        assert_eq!(eval("call mode with ([0.5, 2, 0.5])"), Object::Float(0.5));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn statistics_of_an_empty_list_are_errors() {
+        for name in ["mean", "median", "mode"] {
+            assert_eq!(error(&format!("call {} with ([])", name)), format!("Cannot call s_{} on an empty list", name));
+        }
+    }
+
+    #[test]
+    fn sleep_waits_at_least_the_given_time() {
+        let start = std::time::Instant::now();
+        assert_eq!(eval("call sleep with (20)"), Object::Null);
+        assert!(start.elapsed() >= std::time::Duration::from_millis(20));
+    }
+
+    #[test]
+    fn sleep_rejects_a_negative_duration() {
+        assert_eq!(error("call sleep with (0 - 5)"), "Cannot sleep for a negative duration: -5");
+    }
+
+    #[test]
+    fn op_applies_the_named_operator() {
+        assert_eq!(eval("call op with (\"+\", 2, 3)"), Object::Integer(5));
+        assert_eq!(eval("call op with (\"<\", 1, 2)"), Object::Boolean(true));
+        assert_eq!(eval("call op with (\"%\", 7, 4)"), Object::Integer(3));
+    }
+
+    #[test]
+    fn op_rejects_an_unknown_operator() {
+        assert_eq!(error("call op with (\"^\", 2, 3)"), "Unknown operator for s_op: ^");
+    }
+
+    #[test]
+    fn chars_round_trip_a_multi_byte_string() {
+        assert_eq!(eval("call string_to_chars with (\"né🎉\")"), Object::List(vec![string("n"), string("é"), string("🎉")]));
+        assert_eq!(eval("call chars_to_string with (call string_to_chars with (\"né🎉\"))"), string("né🎉"));
+    }
+
+    #[test]
+    fn chars_to_string_rejects_longer_strings() {
+        assert_eq!(error("call chars_to_string with ([\"a\", \"bc\"])"),
+            "Expected a single character in s_chars_to_string, got String(\"bc\")");
+    }
+
+    #[test]
+    fn counts_maps_each_element_to_its_count() {
+        assert_eq!(eval("call counts with ([\"a\", \"b\", \"a\"])"), map(&[("a", Object::Integer(2)), ("b", Object::Integer(1))]));
+        assert_eq!(eval("call counts with ([])"), map(&[]));
+    }
+
+    #[test]
+    fn normalize_whitespace_collapses_runs_and_trims() {
+        assert_eq!(eval("call normalize_whitespace with (\"  a   b  \")"), string("a b"));
+        assert_eq!(eval("call normalize_whitespace with (\"a\\t\\tb\\n c\")"), string("a b c"));
+        assert_eq!(eval("call normalize_whitespace with (\"a b\")"), string("a b"));
+    }
+
+    #[test]
+    fn in_range_includes_the_start_but_not_the_end() {
+        let source = "[call in_range with (3, 1, 5), call in_range with (7, 1, 5), call in_range with (1, 1, 5), \
+            call in_range with (5, 1, 5)]";
+        assert_eq!(eval(source), Object::List(vec![
+            Object::Boolean(true), Object::Boolean(false), Object::Boolean(true), Object::Boolean(false),
+        ]));
+    }
+
+    #[test]
+    fn get_or_falls_back_to_the_default() {
+        assert_eq!(eval("call get_or with ([1, 2, 3], 1, 0)"), Object::Integer(2));
+        assert_eq!(eval("call get_or with ([1, 2, 3], 3, 0)"), Object::Integer(0));
+        assert_eq!(eval("call get_or with ([1, 2, 3], 0 - 1, 0)"), Object::Integer(0));
+        assert_eq!(eval("call get_or with (\"héllo\", 1, \"?\")"), string("é"));
+        assert_eq!(eval("call get_or with (\"hi\", 5, \"?\")"), string("?"));
+    }
+
+    #[test]
+    fn deep_equals_compares_nested_structures_by_value() {
+        let nested = "call zip_to_map with ([\"a\", \"b\"], [[1, [2, 3]], \"x\"])";
+        assert_eq!(eval(&format!("call deep_equals with ({}, {})", nested, nested)), Object::Boolean(true));
+        let other = "call zip_to_map with ([\"a\", \"b\"], [[1, [2, 4]], \"x\"])";
+        assert_eq!(eval(&format!("call deep_equals with ({}, {})", nested, other)), Object::Boolean(false));
+        assert_eq!(eval("call deep_equals with ([1, [2]], [1, [2], 3])"), Object::Boolean(false));
+    }
+
+    #[test]
+    fn deep_equals_compares_numbers_like_eq() {
+        assert_eq!(eval("[call deep_equals with (1, 1.0), 1 == 1.0]").to_string(), "[true, true]");
+        assert_eq!(eval("call deep_equals with ([1, [2.5]], [1.0, [2.5]])"), Object::Boolean(true));
+        assert_eq!(eval("call deep_equals with (1, \"1\")"), Object::Boolean(false));
+        assert_eq!(eval("call difference with ([1, 2], [1.0])").to_string(), "[2]");
+    }
+
+    #[test]
+    fn group_digits_separates_thousands() {
+        assert_eq!(eval("call group_digits with (1234567)"), string("1,234,567"));
+        assert_eq!(eval("call group_digits with (0 - 1234567)"), string("-1,234,567"));
+        assert_eq!(eval("call group_digits with (999)"), string("999"));
+        assert_eq!(eval("call group_digits with (1000, \" \")"), string("1 000"));
+        assert_eq!(eval("call group_digits with (0 - 2147483647 - 1)"), string("-2,147,483,648"));
+    }
+
+    #[test]
+    fn get_path_and_set_path_on_a_two_level_structure() {
+        let source = "set empty to call zip_to_map with ([], []); \
+            set config to call set_path with (empty, [\"db\", \"port\"], 5432); \
+            [call get_path with (config, [\"db\", \"port\"]), call get_path with (config, [\"db\", \"host\"]), \
+            call get_path with (empty, [\"db\"])]";
+        assert_eq!(eval(source), Object::List(vec![Object::Integer(5432), Object::Null, Object::Null]));
+        let source = "set rows to [[1, 2], [3, 4]]; set changed to call set_path with (rows, [1, 0], 9); [rows, changed]";
+        assert_eq!(eval(source).to_string(), "[[[1, 2], [3, 4]], [[1, 2], [9, 4]]]");
+    }
+
+    #[test]
+    fn set_path_rejects_an_index_out_of_range() {
+        assert_eq!(error("call set_path with ([1], [3], 0)"), "Index 3 out of range in s_set_path");
+    }
+
+    #[test]
+    fn timestamps_round_trip_through_parts() {
+        assert_eq!(eval("call timestamp_to_parts with (1700000000)"), map(&[
+            ("year", Object::Integer(2023)), ("month", Object::Integer(11)), ("day", Object::Integer(14)),
+            ("hour", Object::Integer(22)), ("minute", Object::Integer(13)), ("second", Object::Integer(20)),
+        ]));
+        for secs in ["1700000000", "0", "951782400", "0 - 86401"] {
+            let source = format!("call parts_to_timestamp with (call timestamp_to_parts with ({}))", secs);
+            assert_eq!(eval(&source), eval(secs));
+        }
+    }
+
+    #[test]
+    fn parts_out_of_range_are_an_error() {
+        let parts = |month: i32, day: i32| format!("call parts_to_timestamp with (call zip_to_map with \
+            ([\"year\", \"month\", \"day\", \"hour\", \"minute\", \"second\"], [2024, {}, {}, 0, 0, 0]))", month, day);
+        assert_eq!(error(&parts(13, 1)), "s_parts_to_timestamp expects a month from 1 to 12, got 13");
+        assert_eq!(error(&parts(2, 30)), "s_parts_to_timestamp expects a day from 1 to 29, got 30");
+        assert_eq!(eval(&parts(2, 29)), Object::Integer(1709164800));
+    }
+
+    #[test]
+    fn chunk_splits_into_sublists() {
+        assert_eq!(eval("call chunk with ([1, 2, 3, 4], 2)").to_string(), "[[1, 2], [3, 4]]");
+        assert_eq!(eval("call chunk with ([1, 2, 3, 4, 5], 2)").to_string(), "[[1, 2], [3, 4], [5]]");
+        assert_eq!(error("call chunk with ([1, 2], 0)"), "Chunk size must be positive, got 0");
+    }
+
+    #[test]
+    fn size_of_counts_bytes() {
+        assert_eq!(eval("call size_of with (\"héllo\")"), Object::Integer(6));
+        assert_eq!(eval("[call size_of with ([]), call size_of with ([\"ab\"]), call size_of with ([\"ab\", \"cd\"])]"),
+            Object::List(vec![Object::Integer(0), Object::Integer(2), Object::Integer(4)]));
+    }
+
+    #[test]
+    fn base64_round_trips() {
+        assert_eq!(eval("call to_base64 with (\"hi\")"), string("aGk="));
+        assert_eq!(eval("call to_base64 with (\"héllo 🎉\")"), string("aMOpbGxvIPCfjok="));
+        for text in ["\"hi\"", "\"Man\"", "\"héllo 🎉\"", "\"\""] {
+            assert_eq!(eval(&format!("call from_base64 with (call to_base64 with ({}))", text)), eval(text));
+        }
+        assert_eq!(error("call from_base64 with (\"a\")"), "Invalid base64 string: \"a\"");
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        assert_eq!(eval("call to_hex_string with (\"héllo 🎉\")"), string("68c3a96c6c6f20f09f8e89"));
+        for text in ["\"hi\"", "\"héllo 🎉\""] {
+            assert_eq!(eval(&format!("call from_hex_string with (call to_hex_string with ({}))", text)), eval(text));
+        }
+        assert_eq!(error("call from_hex_string with (\"zz\")"), "Invalid hex string: \"zz\"");
+    }
+
+    #[test]
+    fn equal_structures_hash_equally() {
+        let ab = "call zip_to_map with ([\"a\", \"b\"], [1, [2, 3]])";
+        let ba = "call zip_to_map with ([\"b\", \"a\"], [[2, 3], 1])";
+        assert_eq!(eval(&format!("call hash with ({})", ab)), eval(&format!("call hash with ({})", ba)));
+        assert_ne!(eval("call hash with ([1, 2])"), eval("call hash with ([2, 1])"));
+        assert_ne!(eval("call hash with (1)"), eval("call hash with (\"1\")"));
+    }
+
+    #[test]
+    fn interpolate_reads_variables_from_the_calling_scope() {
+        assert_eq!(eval("set name to \"Ada\"; set n to 3; call interpolate with (\"{name} has {n} items\")"),
+            string("Ada has 3 items"));
+        assert_eq!(eval("set greet to func takes (who) { call interpolate with (\"hi {who}\") }; call greet with (\"Bo\")"),
+            string("hi Bo"));
+    }
+
+    #[test]
+    fn interpolate_rejects_an_undefined_variable() {
+        assert_eq!(error("call interpolate with (\"hi {nobody}\")"), "Variable nobody referenced in s_interpolate is not defined");
+    }
+
+    #[test]
+    fn sorted_keys_are_in_lexicographic_order() {
+        let source = "call sorted_keys with (call zip_to_map with ([\"pear\", \"apple\", \"fig\", \"Zed\"], [1, 2, 3, 4]))";
+        assert_eq!(eval(source), Object::List(vec![string("Zed"), string("apple"), string("fig"), string("pear")]));
+    }
+
+    #[test]
+    fn coalesce_returns_the_first_non_null() {
+        let none = "set none to call print; ";
+        assert_eq!(eval(&format!("{}call coalesce with (none, none, 2, 3)", none)), Object::Integer(2));
+        assert_eq!(eval(&format!("{}call coalesce with (1, none)", none)), Object::Integer(1));
+        assert_eq!(eval(&format!("{}call coalesce with (none, none)", none)), Object::Null);
+        assert_eq!(eval(&format!("{}call coalesce with (none, false)", none)), Object::Boolean(false));
+    }
+
+    #[test]
+    fn lines_drops_the_terminators() {
+        let expected = Object::List(vec![string("a"), string("b"), string("c")]);
+        // a raw \r, since string literals have no escape for it
+        assert_eq!(eval("call lines with (\"a\\nb\r\\nc\")"), expected);
+        assert_eq!(eval("call lines with (\"a\\nb\r\\nc\\n\")"), expected);
+        assert_eq!(eval("call lines with (\"a\\n\\nb\")"), Object::List(vec![string("a"), string(""), string("b")]));
+    }
+
+    #[test]
+    fn format_table_aligns_columns() {
+        let source = "call format_table with ([[\"name\", \"qty\", \"price\"], [\"apple\", 3, 1.25]])";
+        assert_eq!(eval(source), string("name   qty  price\napple  3    1.25"));
+        assert_eq!(eval("call format_table with ([[\"a\", \"bb\"], [\"ccc\"]])"), string("a    bb\nccc"));
+    }
+
+    #[test]
+    fn matches_and_find_all() {
+        assert_eq!(eval("call matches with (\"abc123\", \"[0-9]+\")"), Object::Boolean(true));
+        assert_eq!(eval("call matches with (\"abc\", \"[0-9]+\")"), Object::Boolean(false));
+        assert_eq!(eval("call find_all with (\"a1b22c333\", \"[0-9]+\")"), Object::List(vec![string("1"), string("22"), string("333")]));
+        assert_eq!(eval("call find_all with (\"abc\", \"[0-9]+\")"), Object::List(Vec::new()));
+    }
+
+    #[test]
+    fn matches_rejects_an_invalid_pattern() {
+        assert!(error("call matches with (\"abc\", \"(\")").starts_with("Invalid pattern \"(\" in s_matches"));
+        assert!(error("call find_all with (\"abc\", \"(\")").starts_with("Invalid pattern \"(\" in s_find_all"));
+    }
+
+    #[test]
+    fn regex_replace_supports_capture_references() {
+        assert_eq!(eval("call regex_replace with (\"a-b-c\", \"-\", \"+\")"), string("a+b+c"));
+        assert_eq!(eval("call regex_replace with (\"2024-01-05\", \"(\\\\d+)-(\\\\d+)-(\\\\d+)\", \"$3/$2/$1\")"), string("05/01/2024"));
+    }
+
+    #[test]
+    fn regex_split_on_whitespace_runs() {
+        assert_eq!(eval("call regex_split with (\"a  b\\t\\tc \\n d\", \"\\\\s+\")"),
+            Object::List(vec![string("a"), string("b"), string("c"), string("d")]));
+        assert_eq!(error("call regex_split with (\"ab\", \"x*\")"), "Pattern \"x*\" in s_regex_split matches empty text");
+    }
+
+    #[test]
+    fn settings_reflect_the_interpreter() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(eval_in(&mut interpreter, "call settings").unwrap(), map(&[("warnings", Object::Boolean(false))]));
+        interpreter.enable_warnings();
+        assert_eq!(eval_in(&mut interpreter, "call settings").unwrap(), map(&[("warnings", Object::Boolean(true))]));
+    }
+
+    #[test]
+    fn pretty_indents_nested_lists_and_maps() {
+        let source = "call pretty with (call zip_to_map with ([\"b\", \"a\"], [[], [1, [2]]]))";
+        assert_eq!(eval(source), string("{\n  a: [\n    1,\n    [\n      2\n    ]\n  ],\n  b: []\n}"));
+        assert_eq!(eval("call pretty with (\"x\")"), string("x"));
+    }
+
+    #[test]
+    fn bool_to_int_and_int_to_bool() {
+        assert_eq!(eval("[call bool_to_int with (true), call bool_to_int with (false)]"),
+            Object::List(vec![Object::Integer(1), Object::Integer(0)]));
+        assert_eq!(eval("[call int_to_bool with (0), call int_to_bool with (7), call int_to_bool with (0 - 1)]"),
+            Object::List(vec![Object::Boolean(false), Object::Boolean(true), Object::Boolean(true)]));
+        assert_eq!(error("call int_to_bool with (\"1\")"), "s_int_to_bool expects a single integer, got [String(\"1\")]");
+        assert_eq!(error("call bool_to_int with (1)"), "s_bool_to_int expects a single boolean, got [Integer(1)]");
+    }
+
+    #[test]
+    fn rotate_wraps_by_the_length() {
+        assert_eq!(eval("call rotate with ([1, 2, 3, 4], 1)").to_string(), "[2, 3, 4, 1]");
+        assert_eq!(eval("call rotate with ([1, 2, 3, 4], 0 - 1)").to_string(), "[4, 1, 2, 3]");
+        assert_eq!(eval("call rotate with ([1, 2, 3, 4], 6)").to_string(), "[3, 4, 1, 2]");
+        assert_eq!(eval("call rotate with ([], 3)"), Object::List(Vec::new()));
+    }
+
+    #[test]
+    fn gcd_and_lcm() {
+        let source = "[call gcd with (9, 28), call lcm with (9, 28), call gcd with (12, 18), call lcm with (12, 18)]";
+        assert_eq!(eval(source), Object::List(vec![Object::Integer(1), Object::Integer(252), Object::Integer(6), Object::Integer(36)]));
+        let source = "[call gcd with (0, 0), call gcd with (0, 5), call lcm with (0, 5)]";
+        assert_eq!(eval(source), Object::List(vec![Object::Integer(0), Object::Integer(5), Object::Integer(0)]));
+        assert_eq!(error("call lcm with (65536, 65537)"), "lcm(65536, 65537) does not fit in an integer");
+    }
+
+    #[test]
+    fn char_range_is_inclusive() {
+        assert_eq!(eval("call char_range with (\"a\", \"e\")"),
+            Object::List(vec![string("a"), string("b"), string("c"), string("d"), string("e")]));
+        assert_eq!(eval("call char_range with (\"e\", \"a\")"), Object::List(Vec::new()));
+        assert_eq!(eval("call char_range with (\"α\", \"γ\")"), Object::List(vec![string("α"), string("β"), string("γ")]));
+    }
+
+    #[test]
+    fn memoize_computes_once_per_distinct_argument() {
+        let source = "set calls to 0; set double to call memoize with (func takes (x) { calls++; x * 2 }); \
+            set results to [call double with (2), call double with (2), call double with (3), call double with (2)]; \
+            [results, calls]";
+        assert_eq!(eval(source).to_string(), "[[4, 4, 6, 4], 2]");
+    }
+
+    #[test]
+    fn os_and_arch_are_named() {
+        assert_eq!(eval("call os"), string(std::env::consts::OS));
+        assert!(matches!(eval("call arch"), Object::String(arch) if !arch.is_empty()));
+    }
+
+    #[test]
+    fn zip_longest_fills_the_shorter_side() {
+        assert_eq!(eval("call zip_longest with ([1, 2, 3], [\"a\"], 0)"), Object::List(vec![
+            Object::List(vec![Object::Integer(1), string("a")]),
+            Object::List(vec![Object::Integer(2), Object::Integer(0)]),
+            Object::List(vec![Object::Integer(3), Object::Integer(0)]),
+        ]));
+        assert_eq!(eval("call zip_longest with ([], [1], \"-\")").to_string(), "[[-, 1]]");
+    }
+
+    #[test]
+    fn scan_keeps_running_totals() {
+        assert_eq!(eval("call scan with ([1, 2, 3])"), Object::List(vec![Object::Integer(1), Object::Integer(3), Object::Integer(6)]));
+        assert_eq!(eval("call scan with ([])"), Object::List(Vec::new()));
+        assert_eq!(eval("call scan with ([1, 1.5, 2])"), Object::List(vec![Object::Integer(1), Object::Float(2.5), Object::Float(4.5)]));
+        assert_eq!(error("call scan with ([2147483647, 1])"), "Integer overflow in Add of 2147483647 and 1");
+        assert_eq!(error("call scan with ([1, \"a\"])"), "s_scan expects a list of numbers, got String(\"a\")");
+    }
+
+    #[test]
+    fn is_callable_recognizes_functions() {
+        let source = "[call is_callable with (func { 1 }), call is_callable with (print), \
+            call is_callable with (call memoize with (print)), call is_callable with (3)]";
+        assert_eq!(eval(source), Object::List(vec![
+            Object::Boolean(true), Object::Boolean(true), Object::Boolean(true), Object::Boolean(false),
+        ]));
+    }
+
+    #[test]
+    fn assert_type_passes_the_value_through() {
+        assert_eq!(eval("call assert_type with (5, \"integer\") + 1"), Object::Integer(6));
+        assert_eq!(eval("call type with ([1])"), string("list"));
+    }
+
+    #[test]
+    fn assert_type_rejects_a_mismatch() {
+        assert_eq!(error("call assert_type with (\"5\", \"integer\")"), "Expected a value of type integer, got string String(\"5\")");
+    }
+
+    #[test]
+    fn resize_truncates_or_pads() {
+        assert_eq!(eval("call resize with ([1, 2, 3], 2, 0)").to_string(), "[1, 2]");
+        assert_eq!(eval("call resize with ([1], 3, 0)").to_string(), "[1, 0, 0]");
+        assert_eq!(error("call resize with ([1], 0 - 1, 0)"), "Cannot resize a list to -1 elements");
+    }
+
+    #[test]
+    fn zip_to_map_pairs_keys_with_values() {
+        assert_eq!(eval("call zip_to_map with ([\"a\", 2], [1, \"b\"])"), map(&[("a", Object::Integer(1)), ("2", string("b"))]));
+        assert_eq!(eval("call zip_to_map with ([\"a\", \"a\"], [1, 2])"), map(&[("a", Object::Integer(2))]));
+        assert_eq!(error("call zip_to_map with ([\"a\"], [1, 2])"), "s_zip_to_map got 1 keys but 2 values");
+    }
+
+    #[test]
+    fn set_operations_keep_the_first_list_order() {
+        assert_eq!(eval("call difference with ([3, 1, 2, 1], [2])").to_string(), "[3, 1, 1]");
+        assert_eq!(eval("call intersection with ([3, 1, 2], [2, 3, 4])").to_string(), "[3, 2]");
+        assert_eq!(eval("call union with ([3, 1], [1, 4, 3, 5])").to_string(), "[3, 1, 4, 5]");
+        assert_eq!(eval("call difference with ([[1], [2]], [[2]])").to_string(), "[[1]]");
+    }
+
+    #[test]
+    fn is_sorted_checks_for_non_decreasing_order() {
+        let source = "[call is_sorted with ([1, 2, 2, 3.5]), call is_sorted with ([2, 1]), call is_sorted with ([7]), \
+            call is_sorted with ([\"a\", \"b\"]), call is_sorted with ([3, 2, 1], func takes (a, b) { a >= b })]";
+        assert_eq!(eval(source), Object::List(vec![
+            Object::Boolean(true), Object::Boolean(false), Object::Boolean(true), Object::Boolean(true), Object::Boolean(true),
+        ]));
+        assert_eq!(error("call is_sorted with ([1, \"a\"])"),
+            "s_is_sorted expects only numbers or only strings, got [Integer(1), String(\"a\")]");
+    }
+
+    #[test]
+    fn swap_exchanges_two_elements_in_a_copy() {
+        assert_eq!(eval("set xs to [1, 2, 3]; [call swap with (xs, 0, 2), xs]").to_string(), "[[3, 2, 1], [1, 2, 3]]");
+        assert_eq!(error("call swap with ([1, 2], 0, 5)"), "Index 5 out of range in s_swap");
+    }
+
+    #[test]
+    fn clamp_report_says_whether_it_clamped() {
+        let source = "[call clamp_report with (5, 0, 10), call clamp_report with (0 - 3, 0, 10), \
+            call clamp_report with (12, 0, 10), call clamp_report with (1.5, 0, 1)]";
+        assert_eq!(eval(source).to_string(), "[[5, false], [0, true], [10, true], [1.0, true]]");
+    }
+
+    #[test]
+    fn trim_chars_strips_the_given_characters() {
+        assert_eq!(eval("call trim_chars with (\"--, hi! ,--\", \"-, !\")"), string("hi"));
+        assert_eq!(eval("call trim_start with (\"..a..\", \".\")"), string("a.."));
+        assert_eq!(eval("call trim_end with (\"..a..\", \".\")"), string("..a"));
+        assert_eq!(eval("call trim_chars with (\"«é»\", \"«»\")"), string("é"));
+    }
+
+    #[test]
+    fn flatten_map_joins_paths_with_dots() {
+        let source = "set inner to call zip_to_map with ([\"b\", \"c\"], [1, [2, 3]]); \
+            call flatten_map with (call zip_to_map with ([\"a\", \"d\"], [inner, 4]))";
+        assert_eq!(eval(source), map(&[
+            ("a.b", Object::Integer(1)), ("a.c.0", Object::Integer(2)), ("a.c.1", Object::Integer(3)), ("d", Object::Integer(4)),
+        ]));
+    }
+
+    #[test]
+    fn iota_counts_up_from_the_start() {
+        assert_eq!(eval("call iota with (3)").to_string(), "[0, 1, 2]");
+        assert_eq!(eval("call iota with (5, 3)").to_string(), "[5, 6, 7]");
+        assert_eq!(eval("call iota with (0)"), Object::List(Vec::new()));
+    }
+
+    #[test]
+    fn pad_left_and_pad_right() {
+        assert_eq!(eval("call pad_left with (\"7\", 3, \"0\")"), string("007"));
+        assert_eq!(eval("call pad_right with (\"é\", 3, \"·\")"), string("é··"));
+        assert_eq!(eval("call pad_left with (\"long\", 2, \" \")"), string("long"));
+        assert_eq!(error("call pad_left with (\"a\", 3, \"ab\")"),
+            "s_pad_left expects a string, a width and a single fill character, got [String(\"a\"), Integer(3), String(\"ab\")]");
+    }
+
+    #[test]
+    fn node_count_grows_with_the_body() {
+        let one_liner = eval("call node_count with (func { 1 })");
+        let longer = eval("call node_count with (func takes (x) { set y to x * 2; return y + 1 })");
+        assert_eq!((one_liner, longer), (Object::Integer(4), Object::Integer(10)));
+        assert!(error("call node_count with (print)").starts_with("s_node_count expects a function defined in the script"));
+    }
+
+    #[test]
+    fn merge_combines_nested_maps_and_replaces_the_rest() {
+        let source = "set z to call zip_to_map with ([], []); \
+            set base to call set_path with (call set_path with (z, [\"db\", \"host\"], \"localhost\"), [\"tags\"], [1, 2]); \
+            set over to call set_path with (call set_path with (z, [\"db\", \"port\"], 6543), [\"tags\"], [3]); \
+            [call merge with (base, over), base]";
+        assert_eq!(eval(source), Object::List(vec![
+            map(&[
+                ("db", map(&[("host", string("localhost")), ("port", Object::Integer(6543))])),
+                ("tags", Object::List(vec![Object::Integer(3)])),
+            ]),
+            map(&[
+                ("db", map(&[("host", string("localhost"))])),
+                ("tags", Object::List(vec![Object::Integer(1), Object::Integer(2)])),
+            ]),
+        ]));
+    }
+
+    #[test]
+    fn combinations_and_permutations() {
+        assert_eq!(eval("call combinations with ([1, 2, 3], 2)").to_string(), "[[1, 2], [1, 3], [2, 3]]");
+        assert_eq!(eval("call permutations with ([1, 2])").to_string(), "[[1, 2], [2, 1]]");
+        assert_eq!(error("call permutations with (call iota with (12))"), "s_permutations would produce more than 100000 results");
+    }
+
+    #[test]
+    fn argmin_and_argmax_prefer_the_first_on_ties() {
+        assert_eq!(eval("[call argmin with ([4, 1.5, 9]), call argmax with ([4, 1.5, 9])]").to_string(), "[1, 2]");
+        assert_eq!(eval("[call argmin with ([2, 1, 1]), call argmax with ([3, 1, 3])]").to_string(), "[1, 0]");
+        assert_eq!(error("call argmax with ([])"), "Cannot call s_argmax on an empty list");
+    }
+
+    #[test]
+    fn generate_passes_the_index_when_the_function_takes_one() {
+        assert_eq!(eval("call generate with (4, func takes (i) { i * i })").to_string(), "[0, 1, 4, 9]");
+        assert_eq!(eval("set n to 0; call generate with (3, func { n++; n })").to_string(), "[1, 2, 3]");
+        assert_eq!(error("call generate with (0 - 1, func { 1 })"), "s_generate got a negative count -1");
+    }
+
+    #[test]
+    fn prefix_matching_over_lists_of_strings() {
+        assert_eq!(eval("call any_starts_with with ([\"apple\", \"banana\"], \"ba\")"), Object::Boolean(true));
+        assert_eq!(eval("call any_starts_with with ([\"apple\"], \"ba\")"), Object::Boolean(false));
+        assert_eq!(eval("call filter_prefix with ([\"apple\", \"apricot\", \"banana\"], \"ap\")").to_string(), "[apple, apricot]");
+        assert_eq!(eval("call filter_prefix with ([\"apple\"], \"z\")"), Object::List(vec![]));
+        assert_eq!(error("call filter_prefix with ([\"apple\", 3], \"a\")"), "s_filter_prefix expects a list of strings, got Integer(3)");
+        assert!(error("call any_starts_with with ([\"a\"], 3)").starts_with("s_any_starts_with expects a list of strings and a prefix"));
+    }
+}
@@ -1,164 +1,459 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
 use std::mem;
 
-use crate::builtins::to_bool;
+use crate::builtins::{to_bool, type_name};
 use crate::object::Object;
-use crate::parser::{Expr, ExprOp, Factor, Program, Statement, Term, TermOp};
+use crate::parser::{CompOp, Expr, ExprOp, Factor, LogicOp, Program, Statement, Term, TermOp};
 use crate::scope::Scope;
 
+/// called on every `set`/`change` with the variable name, its previous value (if any), and its new value
+pub type Watcher = Box<dyn FnMut(&str, Option<&Object>, &Object)>;
+
+/// a saved copy of the interpreter's scope chain and warning settings, see Interpreter::snapshot
+#[derive(Debug, Clone)]
+pub struct InterpreterState {
+    scope: Scope,
+    warnings: Option<Vec<String>>,
+}
+
+/// an error raised while evaluating a program, such as an undefined variable or a bad argument to a builtin
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub message: String,
+}
+
+impl RuntimeError {
+    pub fn new(message: impl Into<String>) -> RuntimeError {
+        RuntimeError { message: message.into() }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for RuntimeError {}
+
+/// how a statement finished: normally with its value, or by hitting a return, break or continue
+#[derive(Debug, Clone, PartialEq)]
+pub enum Flow {
+    Normal(Object),
+    Return(Object),
+    /// with the label of the loop it targets, or None for the innermost loop
+    Break(Option<String>),
+    Continue(Option<String>),
+}
+
+impl Flow {
+    /// the value of a statement whose flow ends here, as at a function call or a block used as a value
+    fn into_value(self) -> Result<Object, RuntimeError> {
+        match self {
+            Flow::Normal(value) | Flow::Return(value) => Ok(value),
+            Flow::Break(None) => Err(RuntimeError::new("break used outside of a loop")),
+            Flow::Continue(None) => Err(RuntimeError::new("continue used outside of a loop")),
+            Flow::Break(Some(label)) | Flow::Continue(Some(label)) => {
+                Err(RuntimeError::new(format!("No enclosing loop is labeled {}", label)))
+            }
+        }
+    }
+}
+
 pub struct Interpreter {
     current_scope: Scope,
+    watcher: Option<Watcher>,
+    /// None until warnings are enabled
+    warnings: Option<Vec<String>>,
+    /// how many calls to script-defined functions are in progress
+    call_depth: usize,
+    /// command-line arguments passed after the script name, exposed through `args()`
+    script_args: Vec<String>,
 }
 
 impl Interpreter {
     pub fn new() -> Interpreter {
-        Interpreter { current_scope: Scope::new_root() }
+        Interpreter {
+            current_scope: Scope::new_root(),
+            watcher: None,
+            warnings: None,
+            call_depth: 0,
+            script_args: Vec::new(),
+        }
+    }
+
+    /// starts collecting warnings about suspicious code, retrieved with Interpreter::warnings
+    pub fn enable_warnings(&mut self) {
+        self.warnings.get_or_insert_with(Vec::new);
+    }
+
+    pub fn warnings_enabled(&self) -> bool {
+        self.warnings.is_some()
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        self.warnings.as_deref().unwrap_or(&[])
+    }
+
+    /// how many calls to script-defined functions are in progress, 0 at the top level
+    pub fn call_depth(&self) -> usize {
+        self.call_depth
     }
 
-    pub fn eval_program(&mut self, program: &Program) -> Object {
-        self.eval_statement(&program.body)
+    pub fn set_script_args(&mut self, args: Vec<String>) {
+        self.script_args = args;
+    }
+
+    pub fn script_args(&self) -> &[String] {
+        &self.script_args
+    }
+
+    /// looks up a variable from the scope that is currently executing
+    pub fn lookup(&self, name: &str) -> Option<Object> {
+        self.current_scope.get(name)
+    }
+
+    fn warn(&mut self, message: String) {
+        if let Some(warnings) = &mut self.warnings {
+            warnings.push(message);
+        }
+    }
+
+    /// registers a callback fired after every variable assignment, replacing any previous one
+    pub fn watch(&mut self, watcher: impl FnMut(&str, Option<&Object>, &Object) + 'static) {
+        self.watcher = Some(Box::new(watcher));
+    }
+
+    /// captures every variable in the current scope chain, and whether warnings are on along with
+    /// the ones collected so far
+    pub fn snapshot(&self) -> InterpreterState {
+        InterpreterState { scope: self.current_scope.detached_copy(), warnings: self.warnings.clone() }
+    }
+
+    /// puts the scope chain and warnings back to how they were when the snapshot was taken. the state
+    /// is copied again, so the same snapshot can be restored any number of times
+    pub fn restore(&mut self, state: InterpreterState) {
+        self.current_scope = state.scope.detached_copy();
+        self.warnings = state.warnings;
+    }
+
+    /// a program that is a block gives the value of its trailing expression, like a function body
+    pub fn eval_program(&mut self, program: &Program) -> Result<Object, RuntimeError> {
+        match &program.body {
+            Statement::BlockStatement { statements } => self.eval_function_body(statements)?.into_value(),
+            body => self.eval_statement(body),
+        }
+    }
+
+    /// runs statements directly in the current scope, so the variables they set outlast the call.
+    /// like a function body, a return or a trailing expression gives the value
+    pub fn eval_in_scope(&mut self, statements: &[Statement]) -> Result<Object, RuntimeError> {
+        self.eval_block_statements(statements, true)?.into_value()
     }
 
     /// create a new scope with self.current_scope as its parent and set self.current_scope to it
     pub fn extend_scope(&mut self) {
-        self.current_scope = mem::replace(&mut self.current_scope, Scope::new_empty()).extend();
+        self.current_scope = self.current_scope.extend();
     }
 
     /// replace self.current_scope with its parent
     pub fn retrieve_scope(&mut self) {
-        self.current_scope = mem::replace(&mut self.current_scope, Scope::new_empty()).retrieve();
+        self.current_scope = self.current_scope.retrieve();
+    }
+
+    pub fn eval_block_vec(&mut self, statements: &[Statement]) -> Result<Flow, RuntimeError> {
+        self.extend_scope();
+        let result = self.eval_block_statements(statements, false);
+        self.retrieve_scope(); // on every way out, including a return or an error
+        result
     }
 
-    pub fn eval_block_vec(&mut self, statements: &Vec<Statement>) -> Object {
+    /// like eval_block_vec, but a trailing expression statement is returned as if it had `return`
+    fn eval_function_body(&mut self, statements: &[Statement]) -> Result<Flow, RuntimeError> {
         self.extend_scope();
-        for statement in statements {
-            match statement {
-                Statement::Return { statement: ret_stmt } => {
-                    return self.eval_statement(ret_stmt);
+        let result = self.eval_block_statements(statements, true);
+        self.retrieve_scope();
+        result
+    }
+
+    fn eval_block_statements(&mut self, statements: &[Statement], tail_value: bool) -> Result<Flow, RuntimeError> {
+        for (i, statement) in statements.iter().enumerate() {
+            if tail_value && i == statements.len() - 1 {
+                return self.exec_tail_statement(statement);
+            }
+            if let Statement::Expr { expr } = statement {
+                if self.warnings.is_some() && is_pure_expr(expr) {
+                    self.warn(String::from("unused expression result"));
                 }
-                _ => self.eval_statement(statement),
-            };
+            }
+            match self.exec_statement(statement)? {
+                Flow::Normal(_) => (),
+                flow => return Ok(flow),
+            }
         }
-        self.retrieve_scope();
-        Object::Null
+        Ok(Flow::Normal(Object::Null))
+    }
+
+    /// like exec_statement, for the last statement of a function body: a trailing expression or function,
+    /// or one at the end of an if branch or nested block, gives the value as if it had `return`
+    fn exec_tail_statement(&mut self, statement: &Statement) -> Result<Flow, RuntimeError> {
+        Ok(match statement {
+            Statement::Expr { expr } => Flow::Return(self.eval_expr(expr)?),
+            Statement::FunctionDec { .. } => Flow::Return(self.eval_statement(statement)?),
+            Statement::BlockStatement { statements } => self.eval_function_body(statements)?,
+            Statement::If { conditions } => {
+                for (condition, consequent) in conditions {
+                    let taken = match condition {
+                        Some(cond_stmt) => to_bool(&self.eval_statement(cond_stmt)?)?,
+                        None => true,
+                    };
+                    if taken {
+                        return self.exec_tail_statement(consequent);
+                    }
+                }
+                Flow::Normal(Object::Null)
+            }
+            statement => match self.exec_statement(statement)? {
+                Flow::Normal(_) => Flow::Normal(Object::Null),
+                flow => flow,
+            },
+        })
+    }
+
+    /// runs a statement that may be a return, or contain one inside nested blocks and ifs
+    fn exec_statement(&mut self, statement: &Statement) -> Result<Flow, RuntimeError> {
+        Ok(match statement {
+            Statement::BlockStatement { statements } => self.eval_block_vec(statements)?,
+            Statement::Return { statement: ret_stmt } => Flow::Return(self.eval_statement(ret_stmt)?),
+            Statement::If { conditions } => {
+                for condition in conditions {
+                    match condition {
+                        (Some(cond_stmt), consequent) => {
+                            if to_bool(&self.eval_statement(cond_stmt)?)? {
+                                return self.exec_statement(consequent);
+                            }
+                        }
+                        (None, consequent) => return self.exec_statement(consequent),
+                    }
+                };
+                Flow::Normal(Object::Null)
+            }
+            Statement::While { label, condition, body } => {
+                while to_bool(&self.eval_statement(condition)?)? {
+                    match self.exec_statement(body)? { // the body block gets a fresh scope each time
+                        Flow::Normal(_) | Flow::Continue(None) => (),
+                        Flow::Break(None) => break,
+                        Flow::Continue(Some(target)) if label.as_ref() == Some(&target) => (),
+                        Flow::Break(Some(target)) if label.as_ref() == Some(&target) => break,
+                        flow => return Ok(flow), // a return, or a break or continue for an outer loop
+                    }
+                }
+                Flow::Normal(Object::Null)
+            }
+            Statement::Break { label } => Flow::Break(label.clone()),
+            Statement::Continue { label } => Flow::Continue(label.clone()),
+            statement => Flow::Normal(self.eval_statement(statement)?),
+        })
     }
 
-    pub fn eval_statement(&mut self, statement: &Statement) -> Object {
-        match statement {
-            Statement::BlockStatement { statements } => {
-                self.eval_block_vec(statements)
+    /// evaluates a statement for its value. a return stops here rather than leaving the
+    /// enclosing function, so blocks used as values produce what they return
+    pub fn eval_statement(&mut self, statement: &Statement) -> Result<Object, RuntimeError> {
+        Ok(match statement {
+            Statement::BlockStatement { .. } | Statement::Return { .. } | Statement::If { .. } | Statement::While { .. }
+                | Statement::Break { .. } | Statement::Continue { .. } => {
+                self.exec_statement(statement)?.into_value()?
             }
             Statement::Assign { ident, expr, change } => {
-                let val = self.eval_expr(expr);
+                let val = self.eval_expr(expr)?;
+                let old = match self.watcher {
+                    Some(_) => self.current_scope.get(ident),
+                    None => None,
+                };
                 if *change {
                     if !self.current_scope.reassign(ident, &val) {
-                        panic!("Variable {} was reassigned but it does not exist.", ident);
+                        return Err(RuntimeError::new(format!("Variable {} was reassigned but it does not exist.", ident)));
                     }
                 } else {
                     self.current_scope.set(ident, &val);
                 }
+                if let Some(watcher) = &mut self.watcher {
+                    watcher(ident, old.as_ref(), &val);
+                }
                 Object::Null
             }
             Statement::Expr { expr } => {
-                self.eval_expr(expr)
-            }
-            Statement::Return { statement: ret_stmt } => {
-                // this block will not be called unless there is a
-                // return outside of a block
-                self.eval_statement(ret_stmt)
+                self.eval_expr(expr)?
             }
             Statement::FunctionDec { params, body } => {
-                Object::Function(params.clone(), *(body).clone())
+                Object::Function(params.clone(), *(body).clone(), self.current_scope.clone())
             }
             Statement::FunctionCall { func, args } => {
-                match self.eval_statement(func) {
-                    Object::RustFunction(func) => { // evaluate arguments
-                        let obj_args = args.iter().map(|stmt| self.eval_statement(stmt)).collect::<Vec<_>>();
-                        func(obj_args)
-                    }
-                    obj => panic!("Cannot call {:?}", obj),
+                let func = self.eval_statement(func)?;
+                let obj_args = args.iter().map(|stmt| self.eval_statement(stmt)).collect::<Result<Vec<_>, _>>()?; // evaluate arguments
+                self.call_object(func, obj_args)?
+            }
+            Statement::Comparison { left, op, right } => {
+                let (left, right) = (self.eval_expr(left)?, self.eval_expr(right)?);
+                Self::eval_compop(op, left, right)?
+            }
+            Statement::Logical { left, op, right } => {
+                let left = to_bool(&self.eval_expr(left)?)?;
+                Object::Boolean(match op {
+                    LogicOp::And => left && to_bool(&self.eval_expr(right)?)?,
+                    LogicOp::Or => left || to_bool(&self.eval_expr(right)?)?,
+                })
+            }
+            Statement::Not { expr } => Object::Boolean(!to_bool(&self.eval_expr(expr)?)?),
+        })
+    }
+
+    /// calls a function value with already evaluated arguments
+    pub fn call_object(&mut self, func: Object, args: Vec<Object>) -> Result<Object, RuntimeError> {
+        match func {
+            Object::RustFunction(func) => func(self, args),
+            Object::Memoized(func, cache) => {
+                let key = match memo_key(&Object::List(args.clone())) {
+                    Some(key) => key,
+                    None => return self.call_object(*func, args), // arguments with no stable key aren't cached
+                };
+                if let Some(result) = cache.borrow().get(&key) {
+                    return Ok(result.clone());
                 }
+                let result = self.call_object(*func, args)?;
+                cache.borrow_mut().insert(key, result.clone());
+                Ok(result)
             }
-            Statement::If { conditions } => {
-                for condition in conditions {
-                    match condition {
-                        (Some(cond_stmt), consequent) => {
-                            if to_bool(&self.eval_statement(cond_stmt)) {
-                                return self.eval_statement(consequent);
-                            }
-                        }
-                        (None, consequent) => return self.eval_statement(consequent),
+            Object::Function(params, body, captured) => {
+                if params.len() != args.len() {
+                    return Err(RuntimeError::new(format!(
+                        "Function takes {} arguments but was called with {}", params.len(), args.len()
+                    )));
+                }
+                for (param, arg) in params.iter().zip(&args) {
+                    match &param.annotation {
+                        Some(annotation) if type_name(arg) != annotation => return Err(RuntimeError::new(format!(
+                            "Argument {} expects a value of type {}, got {:?}", param.name, annotation, arg
+                        ))),
+                        _ => (),
                     }
+                }
+                // the body runs where the function was defined, with its parameters in a scope of their own
+                let caller_scope = mem::replace(&mut self.current_scope, captured.extend());
+                for (param, arg) in params.iter().zip(args) {
+                    self.current_scope.set(&param.name, &arg);
+                }
+                self.call_depth += 1;
+                let result = match &body {
+                    Statement::BlockStatement { statements } => self.eval_function_body(statements),
+                    body => self.exec_statement(body),
                 };
-                Object::Null
+                self.call_depth -= 1;
+                self.current_scope = caller_scope;
+                result?.into_value()
             }
+            obj => Err(RuntimeError::new(format!("Cannot call {:?}", obj))),
         }
     }
 
-    pub fn eval_expr(&mut self, expr: &Expr) -> Object {
+    pub fn eval_expr(&mut self, expr: &Expr) -> Result<Object, RuntimeError> {
         match expr.terms.len() {
-            0 => Object::Null,
+            0 => Ok(Object::Null),
             _ => {
-                let mut total = self.eval_term(expr.terms.first().unwrap());
+                let mut total = self.eval_term(expr.terms.first().unwrap())?;
                 let mut current_term = 1; // already eval'd first term
                 while current_term < expr.terms.len() {
-                    let right = self.eval_term(expr.terms.get(current_term).unwrap());
-                    total = self.eval_exprop(expr.ops.get(current_term - 1).unwrap(), total, right);
+                    let right = self.eval_term(expr.terms.get(current_term).unwrap())?;
+                    total = Self::eval_exprop(expr.ops.get(current_term - 1).unwrap(), total, right)?;
                     current_term += 1;
                 }
-                total
+                Ok(total)
             }
         }
     }
 
-    pub fn eval_term(&mut self, term: &Term) -> Object {
+    pub fn eval_term(&mut self, term: &Term) -> Result<Object, RuntimeError> {
         match term.factors.len() {
-            0 => Object::Null,
+            0 => Ok(Object::Null),
             _ => {
-                let mut total = self.eval_factor(term.factors.first().unwrap());
+                let mut total = self.eval_factor(term.factors.first().unwrap())?;
                 let mut current_factor = 1; // already eval'd first factor
                 while current_factor < term.factors.len() {
-                    let right = self.eval_factor(term.factors.get(current_factor).unwrap());
-                    total = self.eval_termop(term.ops.get(current_factor - 1).unwrap(), total, right);
+                    let right = self.eval_factor(term.factors.get(current_factor).unwrap())?;
+                    total = Self::eval_termop(term.ops.get(current_factor - 1).unwrap(), total, right)?;
                     current_factor += 1;
                 }
-                total
+                Ok(total)
             }
         }
     }
 
-    pub fn eval_factor(&mut self, factor: &Factor) -> Object {
-        match factor {
+    pub fn eval_factor(&mut self, factor: &Factor) -> Result<Object, RuntimeError> {
+        Ok(match factor {
             Factor::IdentFactor(ident) => self.current_scope.get(ident)
-                .unwrap_or_else(|| panic!("Identifier not found in current scope: {}", ident)),
+                .ok_or_else(|| RuntimeError::new(format!("Identifier not found in current scope: {}", ident)))?,
             Factor::StringFactor(string) => Object::String(string.clone()),
-            Factor::BoolFactor(val) => Object::Boolean(val.clone()),
-            Factor::IntFactor(num) => Object::Integer(num.clone()),
-            Factor::StmtFactor(statement) => self.eval_statement(statement),
-        }
+            Factor::BoolFactor(val) => Object::Boolean(*val),
+            Factor::IntFactor(num) => Object::Integer(*num),
+            Factor::FloatFactor(num) => Object::Float(*num),
+            Factor::StmtFactor(statement) => self.eval_statement(statement)?,
+            Factor::ListFactor(elements) => {
+                Object::List(elements.iter().map(|expr| self.eval_expr(expr)).collect::<Result<_, _>>()?)
+            }
+            Factor::IndexFactor(target, index) => {
+                let target = self.eval_factor(target)?;
+                let index = self.eval_expr(index)?;
+                index_list(&target, &index)?
+            }
+        })
     }
 
-    pub fn eval_termop(&self, op: &TermOp, left: Object, right: Object) -> Object {
-        match (op, &left, &right) {
+    pub fn eval_termop(op: &TermOp, left: Object, right: Object) -> Result<Object, RuntimeError> {
+        Ok(match (op, &left, &right) {
+            (TermOp::Div, Object::Integer(l_num), Object::Integer(0)) => {
+                return Err(RuntimeError::new(format!("Division by zero in {} / 0", l_num)));
+            }
+            (TermOp::Mod, Object::Integer(l_num), Object::Integer(0)) => {
+                return Err(RuntimeError::new(format!("Modulo by zero in {} % 0", l_num)));
+            }
             (op, Object::Integer(l_num), Object::Integer(r_num)) => {
                 Object::Integer(match op {
+                    TermOp::Div => l_num.checked_div(*r_num),
+                    TermOp::Mul => l_num.checked_mul(*r_num),
+                    TermOp::Mod => l_num.checked_rem(*r_num), // takes the sign of the left side, like Rust
+                }.ok_or_else(|| overflow(op, *l_num, *r_num))?)
+            }
+            (op, l_num, r_num) if is_number(l_num) && is_number(r_num) => { // mixed int/float promotes to float
+                let (l_num, r_num) = (to_float(l_num), to_float(r_num));
+                Object::Float(match op {
                     TermOp::Div => l_num / r_num,
                     TermOp::Mul => l_num * r_num,
+                    TermOp::Mod => l_num % r_num,
                 })
             }
             (TermOp::Mul, Object::String(string), Object::Integer(amt)) => {
                 if *amt < 0 {
-                    panic!("Cannot repeat string < 0 times!");
+                    return Err(RuntimeError::new("Cannot repeat string < 0 times!"));
                 }
                 Object::String(string.repeat(*amt as usize))
             }
-            _ => panic!("Unsupported operation {:?} for {:?} and {:?}", op, left, right),
-        }
+            _ => return Err(unsupported(op, &left, &right)),
+        })
     }
 
-    pub fn eval_exprop(&self, op: &ExprOp, left: Object, right: Object) -> Object {
-        match (op, &left, &right) {
+    pub fn eval_exprop(op: &ExprOp, left: Object, right: Object) -> Result<Object, RuntimeError> {
+        Ok(match (op, &left, &right) {
             (op, Object::Integer(l_num), Object::Integer(r_num)) => {
                 Object::Integer(match op {
+                    ExprOp::Add => l_num.checked_add(*r_num),
+                    ExprOp::Sub => l_num.checked_sub(*r_num),
+                }.ok_or_else(|| overflow(op, *l_num, *r_num))?)
+            }
+            (op, l_num, r_num) if is_number(l_num) && is_number(r_num) => {
+                let (l_num, r_num) = (to_float(l_num), to_float(r_num));
+                Object::Float(match op {
                     ExprOp::Add => l_num + r_num,
                     ExprOp::Sub => l_num - r_num,
                 })
@@ -168,7 +463,427 @@ impl Interpreter {
                 new_str.push_str(r_string.as_str());
                 Object::String(new_str)
             }
-            _ => panic!("Unsupported operation {:?} for {:?} and {:?}", op, left, right),
+            _ => return Err(unsupported(op, &left, &right)),
+        })
+    }
+
+    pub fn eval_compop(op: &CompOp, left: Object, right: Object) -> Result<Object, RuntimeError> {
+        Ok(match (op, &left, &right) {
+            (op, l_num, r_num) if is_number(l_num) && is_number(r_num) => { // 1 == 1.0
+                let (l_num, r_num) = (to_float(l_num), to_float(r_num));
+                Object::Boolean(match op {
+                    CompOp::Eq => l_num == r_num,
+                    CompOp::Neq => l_num != r_num,
+                    CompOp::Lt => l_num < r_num,
+                    CompOp::Gt => l_num > r_num,
+                    CompOp::Lte => l_num <= r_num,
+                    CompOp::Gte => l_num >= r_num,
+                })
+            }
+            (CompOp::Eq, _, _) => Object::Boolean(left == right),
+            (CompOp::Neq, _, _) => Object::Boolean(left != right),
+            _ => return Err(unsupported(op, &left, &right)),
+        })
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter::new()
+    }
+}
+
+fn unsupported(op: &impl fmt::Debug, left: &Object, right: &Object) -> RuntimeError {
+    RuntimeError::new(format!("Unsupported operation {:?} for {:?} and {:?}", op, left, right))
+}
+
+fn overflow(op: &impl fmt::Debug, left: i32, right: i32) -> RuntimeError {
+    RuntimeError::new(format!("Integer overflow in {:?} of {} and {}", op, left, right))
+}
+
+/// a negative index counts back from the end, so list[0 - 1] is the last element
+fn index_list(target: &Object, index: &Object) -> Result<Object, RuntimeError> {
+    match (target, index) {
+        (Object::List(list), Object::Integer(i)) => {
+            let position = if *i < 0 { list.len() as i64 + *i as i64 } else { *i as i64 };
+            usize::try_from(position).ok()
+                .and_then(|position| list.get(position))
+                .cloned()
+                .ok_or_else(|| RuntimeError::new(format!("Index {} out of bounds for a list of length {}", i, list.len())))
+        }
+        (Object::List(_), index) => Err(RuntimeError::new(format!("List index must be an integer, got {:?}", index))),
+        (target, _) => Err(RuntimeError::new(format!("Cannot index into {:?}", target))),
+    }
+}
+
+/// a string that two values share only if they are equal, keeping "1", 1 and 1.0 apart.
+/// functions can't be compared by value, so anything holding one has no key
+fn memo_key(obj: &Object) -> Option<String> {
+    Some(match obj {
+        Object::String(string) => format!("{:?}", string),
+        Object::Integer(num) => num.to_string(),
+        Object::Float(num) => format!("{:?}", num), // always has a . or is NaN/inf, unlike an integer
+        Object::Boolean(val) => val.to_string(),
+        Object::Null => String::from("null"),
+        Object::List(list) => format!("[{}]", list.iter().map(memo_key).collect::<Option<Vec<_>>>()?.join(",")),
+        Object::Map(map) => {
+            let mut entries = map.iter()
+                .map(|(key, value)| Some(format!("{:?}:{}", key, memo_key(value)?)))
+                .collect::<Option<Vec<_>>>()?;
+            entries.sort();
+            format!("{{{}}}", entries.join(","))
+        }
+        Object::RustFunction(_) | Object::Function(_, _, _) | Object::Memoized(_, _) => return None,
+    })
+}
+
+fn is_number(obj: &Object) -> bool {
+    matches!(obj, Object::Integer(_) | Object::Float(_))
+}
+
+/// only called once both sides are known to be numbers
+fn to_float(obj: &Object) -> f64 {
+    match obj {
+        Object::Integer(num) => *num as f64,
+        Object::Float(num) => *num,
+        obj => unreachable!("Expected a number, got {:?}", obj),
+    }
+}
+
+/// whether evaluating the expression can have no effect besides producing its value
+fn is_pure_expr(expr: &Expr) -> bool {
+    expr.terms.iter().all(|term| term.factors.iter().all(is_pure_factor))
+}
+
+fn is_pure_factor(factor: &Factor) -> bool {
+    match factor {
+        Factor::StmtFactor(statement) => is_pure_statement(statement),
+        Factor::ListFactor(elements) => elements.iter().all(is_pure_expr),
+        Factor::IndexFactor(target, index) => is_pure_factor(target) && is_pure_expr(index),
+        _ => true,
+    }
+}
+
+fn is_pure_statement(statement: &Statement) -> bool {
+    match statement {
+        Statement::Expr { expr } => is_pure_expr(expr),
+        Statement::BlockStatement { statements } => statements.iter().all(is_pure_statement),
+        Statement::If { conditions } => conditions.iter().all(|(condition, consequent)| {
+            condition.as_ref().is_none_or(is_pure_statement) && is_pure_statement(consequent)
+        }),
+        Statement::Comparison { left, right, .. } | Statement::Logical { left, right, .. } => {
+            is_pure_expr(left) && is_pure_expr(right)
         }
+        Statement::Not { expr } => is_pure_expr(expr),
+        Statement::FunctionDec { .. } => true,
+        Statement::Assign { .. } | Statement::Return { .. } | Statement::FunctionCall { .. } | Statement::While { .. }
+            | Statement::Break { .. } | Statement::Continue { .. } => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Interpreter;
+    use crate::object::Object;
+    use crate::{eval_in, run};
+
+    fn eval(source: &str) -> Object {
+        run(source).unwrap_or_else(|err| panic!("{} failed: {}", source, err))
+    }
+
+    #[test]
+    fn undefined_identifier_is_an_error() {
+        let err = run("set x to 1; x + missing").unwrap_err();
+        assert_eq!(err.to_string(), "Identifier not found in current scope: missing");
+        assert!(run("call missing").is_err());
+    }
+
+    #[test]
+    fn restore_brings_back_variables_each_time() {
+        let mut interpreter = Interpreter::new();
+        eval_in(&mut interpreter, "set a to 2").unwrap();
+        let snapshot = interpreter.snapshot();
+        eval_in(&mut interpreter, "change a to 5").unwrap();
+        interpreter.restore(snapshot.clone());
+        assert_eq!(eval_in(&mut interpreter, "a").unwrap(), Object::Integer(2));
+        eval_in(&mut interpreter, "change a to 7").unwrap();
+        interpreter.restore(snapshot);
+        assert_eq!(eval_in(&mut interpreter, "a").unwrap(), Object::Integer(2));
+    }
+
+    #[test]
+    fn restore_reconnects_functions_to_the_restored_scope() {
+        let mut interpreter = Interpreter::new();
+        eval_in(&mut interpreter, "set n to 1; set get to func { n }").unwrap();
+        let snapshot = interpreter.snapshot();
+        eval_in(&mut interpreter, "change n to 2").unwrap();
+        interpreter.restore(snapshot);
+        assert_eq!(eval_in(&mut interpreter, "n").unwrap(), Object::Integer(1));
+        assert_eq!(eval_in(&mut interpreter, "call get").unwrap(), Object::Integer(1));
+    }
+
+    #[test]
+    fn restore_brings_back_warning_settings() {
+        let mut interpreter = Interpreter::new();
+        let snapshot = interpreter.snapshot();
+        interpreter.enable_warnings();
+        interpreter.restore(snapshot);
+        assert!(!interpreter.warnings_enabled());
+    }
+
+    #[test]
+    fn integer_overflow_is_an_error() {
+        for source in ["2147483647 + 1", "0 - 2147483647 - 2", "46341 * 46341", "(0 - 2147483647 - 1) / (0 - 1)",
+            "(0 - 2147483647 - 1) % (0 - 1)"] {
+            let err = run(source).unwrap_err();
+            assert!(err.to_string().starts_with("Integer overflow"), "{}: {}", source, err);
+        }
+        assert_eq!(eval("2147483646 + 1"), Object::Integer(2147483647));
+    }
+
+    #[test]
+    fn trailing_function_is_the_return_value() {
+        assert_eq!(eval("set f to func takes (x) { func { x } }; set g to call f with (4); call g"), Object::Integer(4));
+    }
+
+    #[test]
+    fn trailing_if_is_the_return_value() {
+        let source = "set f to func takes (c) { if c then 1 else 2 }; [call f with (true), call f with (false)]";
+        assert_eq!(eval(source), Object::List(vec![Object::Integer(1), Object::Integer(2)]));
+        assert_eq!(eval("set f to func { if false then 1 }; call f"), Object::Null);
+    }
+
+    #[test]
+    fn trailing_if_with_block_branches_is_the_return_value() {
+        let source = "set f to func takes (c) { if c then { 1 } else { set y to 2; y } }; [call f with (true), call f with (false)]";
+        assert_eq!(eval(source), Object::List(vec![Object::Integer(1), Object::Integer(2)]));
+        assert_eq!(eval("set f to func { { set y to 3; y * 2 } }; call f"), Object::Integer(6));
+        assert_eq!(eval("if true then { 3 } else { 4 }"), Object::Integer(3));
+    }
+
+    #[test]
+    fn memoize_keeps_arguments_of_different_types_apart() {
+        let source = "set f to call memoize with (func takes (x) { x }); call f with (1); call f with (\"1\")";
+        assert_eq!(eval(source), Object::String(String::from("1")));
+        let source = "set f to call memoize with (func takes (x) { x }); call f with ([1, 2]); call f with (\"1, 2\")";
+        assert_eq!(eval(source), Object::String(String::from("1, 2")));
+    }
+
+    #[test]
+    fn memoize_does_not_cache_function_arguments() {
+        let source = "set f to call memoize with (func takes (g) { call g }); call f with (func { 1 }); call f with (func { 2 })";
+        assert_eq!(eval(source), Object::Integer(2));
+    }
+
+    #[test]
+    fn annotated_parameters_accept_matching_arguments() {
+        let source = "set f to func takes (n: int, s: string, x) { [n, s, x] }; call f with (1, \"a\", 2.5)";
+        assert_eq!(eval(source), Object::List(vec![
+            Object::Integer(1), Object::String(String::from("a")), Object::Float(2.5),
+        ]));
+    }
+
+    #[test]
+    fn annotated_parameters_reject_other_types() {
+        let err = run("set f to func takes (n: int) { n }; call f with (\"1\")").unwrap_err();
+        assert_eq!(err.to_string(), "Argument n expects a value of type integer, got String(\"1\")");
+        assert!(run("set f to func takes (n: number) { n }").is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn labeled_break_leaves_the_outer_loop() {
+        let source = "set i to 0; set hits to 0; \
+            outer: while i < 3 { i++; set j to 0; while j < 3 { j++; hits++; if i == 2 then break outer } }; [i, hits]";
+        assert_eq!(eval(source), Object::List(vec![Object::Integer(2), Object::Integer(4)]));
+    }
+
+    #[test]
+    fn labeled_continue_skips_the_rest_of_the_outer_body() {
+        let source = "set i to 0; set hits to 0; \
+            outer: while i < 3 { i++; set j to 0; while j < 3 { j++; if j == 2 then continue outer; hits++ } }; hits";
+        assert_eq!(eval(source), Object::Integer(3));
+    }
+
+    #[test]
+    fn undefined_label_is_an_error() {
+        let err = run("outer: while true { while true { break inner } }").unwrap_err();
+        assert_eq!(err.to_string(), "No enclosing loop is labeled inner");
+    }
+
+    #[test]
+    fn ternary_result_can_be_assigned() {
+        assert_eq!(eval("set n to 3; set size to \"big\" if n > 2 else \"small\"; size"), Object::String(String::from("big")));
+        assert_eq!(eval("set n to 1; set size to \"big\" if n > 2 else \"small\"; size"), Object::String(String::from("small")));
+    }
+
+    #[test]
+    fn watcher_sees_old_and_new_values() {
+        let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let mut interpreter = Interpreter::new();
+        let log = seen.clone();
+        interpreter.watch(move |name, old, new| log.borrow_mut().push((name.to_string(), old.cloned(), new.clone())));
+        eval_in(&mut interpreter, "set x to 1; change x to 2").unwrap();
+        assert_eq!(*seen.borrow(), vec![
+            (String::from("x"), None, Object::Integer(1)),
+            (String::from("x"), Some(Object::Integer(1)), Object::Integer(2)),
+        ]);
+    }
+
+    #[test]
+    fn increment_and_decrement() {
+        assert_eq!(eval("set x to 5; x++; x++; x--; x"), Object::Integer(6));
+    }
+
+    #[test]
+    fn increment_needs_a_declared_variable() {
+        assert_eq!(run("y++").unwrap_err().to_string(), "Identifier not found in current scope: y");
+    }
+
+    #[test]
+    fn discarded_pure_expression_warns() {
+        let mut interpreter = Interpreter::new();
+        interpreter.enable_warnings();
+        eval_in(&mut interpreter, "1 + 1; set x to 2").unwrap();
+        assert_eq!(interpreter.warnings(), ["unused expression result"]);
+        let mut interpreter = Interpreter::new();
+        interpreter.enable_warnings();
+        eval_in(&mut interpreter, "call print with (1); set x to 2").unwrap();
+        assert!(interpreter.warnings().is_empty());
+    }
+
+    #[test]
+    fn warnings_are_off_by_default() {
+        let mut interpreter = Interpreter::new();
+        eval_in(&mut interpreter, "1 + 1; set x to 2").unwrap();
+        assert!(!interpreter.warnings_enabled());
+        assert!(interpreter.warnings().is_empty());
+    }
+
+    #[test]
+    fn float_arithmetic_and_promotion() {
+        assert_eq!(eval("1.5 + 0.25"), Object::Float(1.75));
+        assert_eq!(eval("1 + 0.5"), Object::Float(1.5));
+        assert_eq!(eval("3.0 / 2"), Object::Float(1.5));
+        assert_eq!(eval("7 / 2"), Object::Integer(3));
+    }
+
+    #[test]
+    fn comparisons_give_booleans() {
+        let source = "[3 < 5, 5 <= 4, 2 > 1, 2 >= 3, \"a\" == \"a\", true != false, 1 == 2]";
+        assert_eq!(eval(source), Object::List(vec![
+            Object::Boolean(true), Object::Boolean(false), Object::Boolean(true), Object::Boolean(false),
+            Object::Boolean(true), Object::Boolean(true), Object::Boolean(false),
+        ]));
+        assert_eq!(eval("set x to 4; if x > 3 then \"big\" else \"small\""), Object::String(String::from("big")));
+    }
+
+    #[test]
+    fn logical_operators_coerce_and_short_circuit() {
+        assert_eq!(eval("[1 and \"hi\", 0 or false, not 0, true and not false]"), Object::List(vec![
+            Object::Boolean(true), Object::Boolean(false), Object::Boolean(true), Object::Boolean(true),
+        ]));
+        assert_eq!(eval("false and 1 / 0"), Object::Boolean(false));
+        assert_eq!(eval("true or missing"), Object::Boolean(true));
+        assert!(run("true and 1 / 0").is_err());
+    }
+
+    #[test]
+    fn modulo() {
+        assert_eq!(eval("10 % 3"), Object::Integer(1));
+        assert_eq!(eval("5.5 % 2"), Object::Float(1.5));
+        assert_eq!(run("10 % 0").unwrap_err().to_string(), "Modulo by zero in 10 % 0");
+        assert_eq!(run("10 / 0").unwrap_err().to_string(), "Division by zero in 10 / 0");
+    }
+
+    #[test]
+    fn user_functions_can_be_called() {
+        assert_eq!(eval("set add to func takes (a, b) { return a + b }; call add with (2, 3)"), Object::Integer(5));
+        assert_eq!(eval("set seven to func { 7 }; call seven"), Object::Integer(7));
+    }
+
+    #[test]
+    fn calling_with_the_wrong_argument_count_is_an_error() {
+        let err = run("set add to func takes (a, b) { a + b }; call add with (1)").unwrap_err();
+        assert_eq!(err.to_string(), "Function takes 2 arguments but was called with 1");
+    }
+
+    #[test]
+    fn early_return_restores_the_caller_scope() {
+        let source = "set total to 10; \
+            set f to func takes (n) { set total to 0; if n > 0 then { { return n } }; n }; \
+            set r to call f with (3); [r, total]";
+        assert_eq!(eval(source), Object::List(vec![Object::Integer(3), Object::Integer(10)]));
+    }
+
+    #[test]
+    fn return_inside_an_if_leaves_the_function() {
+        let source = "set sign to func takes (n) { if n < 0 then { return \"negative\" } elif n == 0 then { return \"zero\" }; \
+            call print with (\"unreachable for n <= 0\"); \"positive\" }; \
+            [call sign with (0 - 2), call sign with (0)]";
+        assert_eq!(eval(source), Object::List(vec![Object::String(String::from("negative")), Object::String(String::from("zero"))]));
+    }
+
+    #[test]
+    fn change_reassigns_an_outer_variable() {
+        assert_eq!(eval("set x to 1; { { change x to 2 } }; x"), Object::Integer(2));
+        assert_eq!(eval("set x to 1; { set x to 5; change x to 6 }; x"), Object::Integer(1));
+        assert_eq!(run("{ change nope to 1 }").unwrap_err().to_string(), "Variable nope was reassigned but it does not exist.");
+    }
+
+    #[test]
+    fn function_bodies_return_their_trailing_expression() {
+        assert_eq!(eval("set f to func takes (x) { set y to x * 2; y + 1 }; call f with (3)"), Object::Integer(7));
+        assert_eq!(eval("set f to func takes (x) { return x; x + 1 }; call f with (3)"), Object::Integer(3));
+        assert_eq!(eval("set f to func { set y to 1 }; call f"), Object::Null);
+    }
+
+    #[test]
+    fn list_literals_and_indexing() {
+        assert_eq!(eval("[1, 2 + 3, [4]]").to_string(), "[1, 5, [4]]");
+        assert_eq!(eval("set xs to [10, 20, 30]; [xs[0], xs[2], xs[0 - 1], xs[0 - 3]]").to_string(), "[10, 30, 30, 10]");
+        assert_eq!(eval("[[1, 2], [3, 4]][1][0]"), Object::Integer(3));
+    }
+
+    #[test]
+    fn indexing_out_of_bounds_is_an_error() {
+        assert_eq!(run("set xs to [1, 2, 3]; xs[3]").unwrap_err().to_string(), "Index 3 out of bounds for a list of length 3");
+        assert!(run("set xs to [1, 2, 3]; xs[0 - 4]").is_err());
+    }
+
+    #[test]
+    fn while_loop_sums_one_to_five() {
+        assert_eq!(eval("set i to 1; set sum to 0; while i <= 5 { change sum to sum + i; i++ }; sum"), Object::Integer(15));
+    }
+
+    #[test]
+    fn loop_body_bindings_do_not_leak() {
+        assert!(run("set i to 0; while i < 2 { i++; set seen to i }; seen").is_err());
+    }
+
+    #[test]
+    fn break_leaves_the_loop() {
+        assert_eq!(eval("set i to 0; while true { i++; if i == 4 then break }; i"), Object::Integer(4));
+    }
+
+    #[test]
+    fn continue_skips_to_the_next_iteration() {
+        let source = "set i to 0; set odds to 0; while i < 6 { i++; if i % 2 == 0 then continue; change odds to odds + i }; odds";
+        assert_eq!(eval(source), Object::Integer(9));
+    }
+
+    #[test]
+    fn break_outside_a_loop_is_an_error() {
+        assert_eq!(run("break").unwrap_err().to_string(), "break used outside of a loop");
+        assert_eq!(run("set f to func { continue }; while true { call f }").unwrap_err().to_string(), "continue used outside of a loop");
+    }
+
+    #[test]
+    fn closures_keep_their_defining_scope() {
+        let source = "set make_counter to func { set count to 0; func { count++; count } }; \
+            set counter to call make_counter; call counter; call counter; \
+            set other to call make_counter; [call counter, call other]";
+        assert_eq!(eval(source), Object::List(vec![Object::Integer(3), Object::Integer(1)]));
+        let source = "set outer to func { set secret to 42; func { secret } }; set inner to call outer; call inner";
+        assert_eq!(eval(source), Object::Integer(42));
+    }
+}
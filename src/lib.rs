@@ -0,0 +1,84 @@
+use std::error::Error;
+use std::fmt;
+
+pub mod token;
+pub mod parser;
+mod scope;
+pub mod object;
+pub mod interpreter;
+pub mod builtins;
+
+use crate::interpreter::{Interpreter, RuntimeError};
+use crate::object::Object;
+use crate::parser::{ParseError, Parser};
+use crate::token::LexError;
+
+/// anything that can go wrong between reading source and getting its value
+#[derive(Debug, Clone)]
+pub enum SuroError {
+    Lex(LexError),
+    Parse(ParseError),
+    Runtime(RuntimeError),
+}
+
+impl fmt::Display for SuroError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SuroError::Lex(err) => write!(f, "{}", err),
+            SuroError::Parse(err) => write!(f, "{}", err),
+            SuroError::Runtime(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for SuroError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SuroError::Lex(err) => Some(err),
+            SuroError::Parse(err) => Some(err),
+            SuroError::Runtime(err) => Some(err),
+        }
+    }
+}
+
+impl From<LexError> for SuroError {
+    fn from(err: LexError) -> SuroError {
+        SuroError::Lex(err)
+    }
+}
+
+impl From<ParseError> for SuroError {
+    fn from(err: ParseError) -> SuroError {
+        SuroError::Parse(err)
+    }
+}
+
+impl From<RuntimeError> for SuroError {
+    fn from(err: RuntimeError) -> SuroError {
+        SuroError::Runtime(err)
+    }
+}
+
+/// runs `;`-separated statements in a fresh interpreter, e.g. `run("set x to 2 + 3; return x")`.
+/// a return or a trailing expression gives the value, as in a function body
+pub fn run(source: &str) -> Result<Object, SuroError> {
+    eval_in(&mut Interpreter::new(), source)
+}
+
+/// like run, but in an existing interpreter, where variables from earlier calls are still set and
+/// the ones set here stay for later calls
+pub fn eval_in(interpreter: &mut Interpreter, source: &str) -> Result<Object, SuroError> {
+    let tokens = token::tokenize(source)?;
+    let statements = Parser::new(tokens).parse_lines()?;
+    Ok(interpreter.eval_in_scope(&statements)?)
+}
+
+/// runs a program file's source in an existing interpreter. the braces around a file don't get a
+/// scope of their own here, so the variables and functions it sets stay available afterwards
+pub fn load_into(interpreter: &mut Interpreter, source: &str) -> Result<Object, SuroError> {
+    let program = Parser::new(token::tokenize(source)?).parse()?;
+    Ok(match &program.body {
+        parser::Statement::BlockStatement { statements } => interpreter.eval_in_scope(statements)?,
+        _ => interpreter.eval_program(&program)?,
+    })
+}
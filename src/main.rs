@@ -1,13 +1,10 @@
 use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::process;
 
 use clap::{App, Arg};
 
-mod token;
-mod parser;
-mod scope;
-mod object;
-mod interpreter;
-mod builtins;
+use suro::{interpreter, object, parser, token};
 
 fn main() {
     let matches = App::new("Suro Interpreter")
@@ -15,13 +12,36 @@ fn main() {
         .author("Matias Kotlik (mdkotlik) <mdkotlik@gmail.com>")
         .about("Compiler for the suro language")
         .arg(Arg::with_name("FILE")
-            .help("The file to run")
-            .required(true)
+            .help("The file to run; without one, statements are read line by line from stdin")
             .index(1))
+        .arg(Arg::with_name("ARGS")
+            .help("Arguments passed to the script, available through args()")
+            .multiple(true)
+            .index(2))
         .arg(Arg::with_name("verbose")
             .short("v")
             .long("verbose")
             .help("Sets verbose mode"))
+        .arg(Arg::with_name("newline-terminators")
+            .long("newline-terminators")
+            .help("Lets a newline end a statement, making ; optional at the end of a line"))
+        .arg(Arg::with_name("warnings")
+            .short("W")
+            .long("warnings")
+            .help("Reports suspicious code, such as expression results that are thrown away"))
+        .arg(Arg::with_name("output-json")
+            .long("output-json")
+            .help("Prints the program's final value as JSON"))
+        .arg(Arg::with_name("eval")
+            .short("e")
+            .long("eval")
+            .value_name("CODE")
+            .conflicts_with("FILE")
+            .help("Runs ;-separated statements given on the command line instead of a file, printing the result"))
+        .arg(Arg::with_name("print-result")
+            .short("p")
+            .long("print-result")
+            .help("Prints the program's final value unless it's null; always on with --eval"))
         .get_matches();
 
     let verbose = matches.is_present("verbose");
@@ -29,18 +49,108 @@ fn main() {
         println!("ASDF");
     }
 
-    let tokens = token::tokenize(&fs::read_to_string(matches.value_of("FILE").unwrap()).unwrap());
+    let mut interpreter = interpreter::Interpreter::new();
+    interpreter.set_script_args(matches.values_of("ARGS")
+        .map(|values| values.map(String::from).collect())
+        .unwrap_or_default());
+    if verbose {
+        interpreter.watch(|name, old, new| match old {
+            Some(old) => println!("Assign: {} = {:?} (was {:?})", name, new, old),
+            None => println!("Assign: {} = {:?}", name, new),
+        });
+    }
+    if matches.is_present("warnings") {
+        interpreter.enable_warnings();
+    }
+
+    let inline = matches.value_of("eval");
+    let source = match (inline, matches.value_of("FILE")) {
+        (Some(code), _) => String::from(code),
+        (None, Some(file)) => fs::read_to_string(file).unwrap(),
+        (None, None) => {
+            repl(&mut interpreter);
+            return;
+        }
+    };
+    let tokens = if matches.is_present("newline-terminators") {
+        token::tokenize_newline_terminated(&source)
+    } else {
+        token::tokenize(&source)
+    }.unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    });
     if verbose {
         println!("Tokens: {:?}", &tokens);
     }
 
-    let program = parser::Parser::new(tokens).parse();
+    let mut parser = parser::Parser::new(tokens);
+    let program = match inline {
+        // code on the command line is a list of statements, like a REPL line, rather than one block
+        Some(_) => parser.parse_lines().map(|statements| parser::Program {
+            body: parser::Statement::BlockStatement { statements },
+        }),
+        None => parser.parse(),
+    }.unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    });
     if verbose {
         println!("Tree: {:#?}", &program);
     }
 
-    let result = interpreter::Interpreter::new().eval_program(&program);
+    let result = interpreter.eval_program(&program);
+    for warning in interpreter.warnings() {
+        eprintln!("warning: {}", warning);
+    }
+    let result = result.unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    });
     if verbose {
         println!("Result: {:?}", &result)
     }
+    if matches.is_present("output-json") {
+        println!("{}", result.to_json());
+    } else if (inline.is_some() || matches.is_present("print-result")) && result != object::Object::Null {
+        println!("{}", result);
+    }
+}
+
+/// runs each line from stdin as its own program, printing any result that isn't null. variables carry over
+/// between lines, and an error is reported without ending the session. `.load path` runs a file into the session
+fn repl(interpreter: &mut interpreter::Interpreter) {
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal(); // no prompts when input is piped in
+    loop {
+        if interactive {
+            print!("> ");
+            io::stdout().flush().unwrap();
+        }
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            break; // end of input
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let seen_warnings = interpreter.warnings().len();
+        let result = match line.trim().strip_prefix(".load ") {
+            Some(path) => load(interpreter, path.trim()),
+            None => suro::eval_in(interpreter, &line).map_err(|err| err.to_string()),
+        };
+        for warning in &interpreter.warnings()[seen_warnings..] {
+            eprintln!("warning: {}", warning);
+        }
+        match result {
+            Ok(object::Object::Null) => (),
+            Ok(value) => println!("{}", value),
+            Err(err) => eprintln!("error: {}", err),
+        }
+    }
+}
+
+fn load(interpreter: &mut interpreter::Interpreter, path: &str) -> Result<object::Object, String> {
+    let source = fs::read_to_string(path).map_err(|err| format!("Cannot read {}: {}", path, err))?;
+    suro::load_into(interpreter, &source).map_err(|err| err.to_string())
 }
@@ -1,12 +1,118 @@
-use crate::parser::Statement;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
 
+use crate::interpreter::{Interpreter, RuntimeError};
+use crate::parser::{Param, Statement};
+use crate::scope::Scope;
+
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(PartialEq, Debug, Clone)]
 pub enum Object {
     String(String),
     Integer(i32),
+    Float(f64),
     Boolean(bool),
-    RustFunction(fn(Vec<Object>) -> Object),
-    Function(Vec<String>, Statement),
+    List(Vec<Object>),
+    Map(HashMap<String, Object>),
+    RustFunction(fn(&mut Interpreter, Vec<Object>) -> Result<Object, RuntimeError>),
+    /// parameters, body, and the scope the function was defined in
+    Function(Vec<Param>, Statement, Scope),
+    /// a callable plus the results it has returned so far, keyed by the stringified arguments.
+    /// copies of the object share the cache
+    Memoized(Box<Object>, Rc<RefCell<HashMap<String, Object>>>),
     Null,
 }
 
+impl Object {
+    /// serializes the object as JSON; functions have no JSON form and become null
+    pub fn to_json(&self) -> String {
+        match self {
+            Object::String(string) => json_string(string),
+            Object::Integer(num) => num.to_string(),
+            Object::Float(num) if !num.is_finite() => String::from("null"),
+            Object::Float(num) => format_float(*num),
+            Object::Boolean(val) => val.to_string(),
+            Object::List(list) => format!("[{}]", list.iter().map(Object::to_json).collect::<Vec<_>>().join(",")),
+            Object::Map(map) => {
+                let mut keys = map.keys().collect::<Vec<_>>();
+                keys.sort();
+                format!("{{{}}}", keys.iter().map(|key| format!("{}:{}", json_string(key), map[*key].to_json())).collect::<Vec<_>>().join(","))
+            }
+            Object::RustFunction(_) | Object::Function(_, _, _) | Object::Memoized(_, _) | Object::Null => String::from("null"),
+        }
+    }
+}
+
+/// how print shows a value: strings without quotes, maps with sorted keys, and functions as placeholders
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Object::String(string) => write!(f, "{}", string),
+            Object::Integer(num) => write!(f, "{}", num),
+            Object::Float(num) => write!(f, "{}", format_float(*num)),
+            Object::Boolean(val) => write!(f, "{}", val),
+            Object::List(list) => write!(f, "[{}]", list.iter().map(Object::to_string).collect::<Vec<_>>().join(", ")),
+            Object::Map(map) => {
+                let mut keys = map.keys().collect::<Vec<_>>();
+                keys.sort();
+                write!(f, "{{{}}}", keys.iter().map(|key| format!("{}: {}", key, map[*key])).collect::<Vec<_>>().join(", "))
+            }
+            Object::RustFunction(_) => write!(f, "<builtin>"),
+            Object::Function(_, _, _) | Object::Memoized(_, _) => write!(f, "<function>"),
+            Object::Null => write!(f, "null"),
+        }
+    }
+}
+
+/// formats a float so it always reads as one, e.g. 2.0 rather than 2
+pub fn format_float(num: f64) -> String {
+    if num.is_finite() && num.fract() == 0.0 {
+        format!("{:.1}", num)
+    } else {
+        num.to_string()
+    }
+}
+
+fn json_string(string: &str) -> String {
+    let mut out = String::from("\"");
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Object;
+    use crate::run;
+
+    fn display(source: &str) -> String {
+        run(source).unwrap_or_else(|err| panic!("{} failed: {}", source, err)).to_string()
+    }
+
+    #[test]
+    fn display_of_each_variant() {
+        assert_eq!(Object::String(String::from("hi")).to_string(), "hi");
+        assert_eq!(Object::Integer(-3).to_string(), "-3");
+        assert_eq!(Object::Float(2.0).to_string(), "2.0");
+        assert_eq!(Object::Float(0.25).to_string(), "0.25");
+        assert_eq!(Object::Boolean(true).to_string(), "true");
+        assert_eq!(Object::Null.to_string(), "null");
+        assert_eq!(display("[1, \"a\", [true]]"), "[1, a, [true]]");
+        assert_eq!(display("call zip_to_map with ([\"b\", \"a\"], [2, 1])"), "{a: 1, b: 2}");
+        assert_eq!(display("print"), "<builtin>");
+        assert_eq!(display("func { 1 }"), "<function>");
+        assert_eq!(display("call memoize with (func { 1 })"), "<function>");
+    }
+}
@@ -1,9 +1,13 @@
+use std::error::Error;
+use std::fmt;
+
 use crate::token::{Token, Type};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum TermOp {
     Mul,
     Div,
+    Mod,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -12,11 +16,35 @@ pub enum ExprOp {
     Sub,
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub enum CompOp {
+    Eq,
+    Neq,
+    Lt,
+    Gt,
+    Lte,
+    Gte,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum LogicOp {
+    And,
+    Or,
+}
+
+/// a function parameter, with the type its argument must have if one was written (`x: int`)
+#[derive(Debug, PartialEq, Clone)]
+pub struct Param {
+    pub name: String,
+    pub annotation: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct Program {
     pub body: Statement,
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, PartialEq, Clone)]
 pub enum Statement {
     Assign {
@@ -25,7 +53,7 @@ pub enum Statement {
         change: bool,
     },
     FunctionDec {
-        params: Vec<String>,
+        params: Vec<Param>,
         body: Box<Statement>,
     },
     Return {
@@ -44,6 +72,33 @@ pub enum Statement {
         func: Box<Statement>,
         args: Vec<Statement>,
     },
+    Comparison {
+        left: Expr,
+        op: CompOp,
+        right: Expr,
+    },
+    /// the right side is only evaluated if the left doesn't decide the result
+    Logical {
+        left: Expr,
+        op: LogicOp,
+        right: Expr,
+    },
+    Not {
+        expr: Expr,
+    },
+    While {
+        /// the name a labeled break or continue uses to target this loop (`outer: while ...`)
+        label: Option<String>,
+        condition: Box<Statement>,
+        body: Box<Statement>,
+    },
+    /// a label targets an enclosing loop by name, otherwise the innermost loop
+    Break {
+        label: Option<String>,
+    },
+    Continue {
+        label: Option<String>,
+    },
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -58,15 +113,110 @@ pub struct Term {
     pub ops: Vec<TermOp>,
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, PartialEq, Clone)]
 pub enum Factor {
     IntFactor(i32),
+    FloatFactor(f64),
     StringFactor(String),
     BoolFactor(bool),
     StmtFactor(Box<Statement>),
     IdentFactor(String),
+    ListFactor(Vec<Expr>),
+    /// `list[index]`
+    IndexFactor(Box<Factor>, Box<Expr>),
+}
+
+impl Statement {
+    /// how many statements, expressions and factors make up this statement, itself included
+    pub fn node_count(&self) -> usize {
+        1 + match self {
+            Statement::Assign { expr, .. } | Statement::Expr { expr } | Statement::Not { expr } => expr.node_count(),
+            Statement::FunctionDec { body, .. } | Statement::Return { statement: body } => body.node_count(),
+            Statement::BlockStatement { statements } => statements.iter().map(Statement::node_count).sum(),
+            Statement::If { conditions } => conditions.iter().map(|(condition, consequent)| {
+                condition.as_ref().map_or(0, Statement::node_count) + consequent.node_count()
+            }).sum(),
+            Statement::FunctionCall { func, args } => func.node_count() + args.iter().map(Statement::node_count).sum::<usize>(),
+            Statement::Comparison { left, right, .. } | Statement::Logical { left, right, .. } => {
+                left.node_count() + right.node_count()
+            }
+            Statement::While { condition, body, .. } => condition.node_count() + body.node_count(),
+            Statement::Break { .. } | Statement::Continue { .. } => 0,
+        }
+    }
+}
+
+impl Expr {
+    /// see Statement::node_count; terms only group factors and aren't counted themselves
+    pub fn node_count(&self) -> usize {
+        1 + self.terms.iter().flat_map(|term| &term.factors).map(Factor::node_count).sum::<usize>()
+    }
+}
+
+impl Factor {
+    pub fn node_count(&self) -> usize {
+        1 + match self {
+            Factor::StmtFactor(statement) => statement.node_count(),
+            Factor::ListFactor(elements) => elements.iter().map(Expr::node_count).sum(),
+            Factor::IndexFactor(target, index) => target.node_count() + index.node_count(),
+            _ => 0,
+        }
+    }
+}
+
+/// decodes \n, \t, \\, \" and \' in a string literal. other escapes are kept as written,
+/// so patterns like "\s+" still reach the regex builtins intact
+fn unescape(literal: &str) -> String {
+    let mut string = String::new();
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            string.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => string.push('\n'),
+            Some('t') => string.push('\t'),
+            Some(escaped @ '\\') | Some(escaped @ '"') | Some(escaped @ '\'') => string.push(escaped),
+            Some(other) => {
+                string.push('\\');
+                string.push(other);
+            }
+            None => string.push('\\'),
+        }
+    }
+    string
+}
+
+/// wraps a statement as an expression with a single factor
+fn statement_expr(statement: Statement) -> Expr {
+    Expr {
+        terms: vec![Term {
+            factors: vec![Factor::StmtFactor(Box::new(statement))],
+            ops: Vec::new(),
+        }],
+        ops: Vec::new(),
+    }
+}
+
+/// a token the grammar doesn't allow where it appears
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    /// what the parser was looking for, e.g. "Terminator" or "a float"
+    pub expected: String,
+    pub found: Token,
 }
 
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Expected {} at line {}, col {}, got {:?} {:?}",
+            self.expected, self.found.line, self.found.col, self.found.token_type, self.found.str)
+    }
+}
+
+impl Error for ParseError {}
+
 pub struct Parser {
     tokens: Vec<Token>,
     index: usize,
@@ -80,45 +230,60 @@ impl Parser {
         }
     }
 
-    pub fn parse(&mut self) -> Program {
+    pub fn parse(&mut self) -> Result<Program, ParseError> {
         self.parse_program()
     }
 
-    fn parse_program(&mut self) -> Program {
+    /// parses `;`-separated statements up to the end of the input, like the inside of a block
+    pub fn parse_lines(&mut self) -> Result<Vec<Statement>, ParseError> {
+        let mut statements = Vec::new();
+        while self.current_unwrap().token_type != Type::EOF {
+            statements.push(self.parse_statement()?);
+            if !self.consume_if(Type::Terminator) { // the last statement may leave off its ;
+                break;
+            }
+        }
+        self.expect_consume(Type::EOF)?;
+        Ok(statements)
+    }
+
+    fn parse_program(&mut self) -> Result<Program, ParseError> {
         let program = Program {
-            body: self.parse_statement(),
+            body: self.parse_statement()?,
         };
-        self.expect_consume(Type::EOF);
-        program
+        self.expect_consume(Type::EOF)?;
+        Ok(program)
     }
 
-    fn parse_block(&mut self) -> Statement {
-        self.expect_consume(Type::BlockStart);
-        Statement::BlockStatement {
+    fn parse_block(&mut self) -> Result<Statement, ParseError> {
+        self.expect_consume(Type::BlockStart)?;
+        Ok(Statement::BlockStatement {
             statements: {
                 let mut lst = Vec::new();
                 while self.current_unwrap().token_type != Type::BlockEnd {
-                    lst.push(self.parse_line());
+                    lst.push(self.parse_line()?);
                 };
-                self.expect_consume(Type::BlockEnd);
+                self.expect_consume(Type::BlockEnd)?;
                 lst
             }
-        }
+        })
     }
 
-    fn parse_line(&mut self) -> Statement {
-        let statement = self.parse_statement();
-        self.expect_consume(Type::Terminator);
-        statement
+    fn parse_line(&mut self) -> Result<Statement, ParseError> {
+        let statement = self.parse_statement()?;
+        if self.current_unwrap().token_type != Type::BlockEnd { // the last line may leave off its ;
+            self.expect_consume(Type::Terminator)?;
+        }
+        Ok(statement)
     }
 
-    fn parse_statement(&mut self) -> Statement {
-        match self.current_unwrap().token_type {
+    fn parse_statement(&mut self) -> Result<Statement, ParseError> {
+        Ok(match self.current_unwrap().token_type {
             Type::Assignment => {
                 self.consume_unwrap(); // consume let
-                let ident = self.expect_consume(Type::Ident).str; // consume and store ident
-                self.expect_consume(Type::AssignmentOp); // consume equals sine
-                let expr = self.parse_expr();
+                let ident = self.expect_consume(Type::Ident)?.str; // consume and store ident
+                self.expect_consume(Type::AssignmentOp)?; // consume equals sine
+                let expr = self.parse_ternary()?;
                 Statement::Assign {
                     ident,
                     expr,
@@ -127,65 +292,217 @@ impl Parser {
             }
             Type::Change => {
                 self.consume_unwrap(); // consume let
-                let ident = self.expect_consume(Type::Ident).str; // consume and store ident
-                self.expect_consume(Type::AssignmentOp); // consume equals sine
-                let expr = self.parse_expr();
+                let ident = self.expect_consume(Type::Ident)?.str; // consume and store ident
+                self.expect_consume(Type::AssignmentOp)?; // consume equals sine
+                let expr = self.parse_ternary()?;
                 Statement::Assign {
                     ident,
                     expr,
                     change: true,
                 }
             }
+            Type::Ident if matches!(self.peek_type(), Some(Type::Increment) | Some(Type::Decrement)) => {
+                let ident = self.consume_unwrap().str;
+                let op = match self.consume_unwrap().token_type {
+                    Type::Increment => ExprOp::Add,
+                    _ => ExprOp::Sub,
+                };
+                Statement::Assign { // x++ is sugar for change x to x + 1
+                    expr: Expr {
+                        terms: vec![
+                            Term { factors: vec![Factor::IdentFactor(ident.clone())], ops: Vec::new() },
+                            Term { factors: vec![Factor::IntFactor(1)], ops: Vec::new() },
+                        ],
+                        ops: vec![op],
+                    },
+                    ident,
+                    change: true,
+                }
+            }
             Type::Return => {
                 self.consume_unwrap(); // consume return
-                Statement::Return { statement: Box::new(self.parse_statement()) }
+                Statement::Return { statement: Box::new(self.parse_statement()?) }
             }
             Type::BlockStart => {
-                self.parse_block()
+                return self.parse_block();
+            }
+            Type::FuncDec => {
+                return self.parse_function();
+            }
+            Type::Ident if self.peek_type() == Some(Type::Colon) => {
+                let label = self.consume_unwrap().str;
+                self.consume_unwrap(); // consume :
+                return self.parse_while(Some(label));
+            }
+            Type::While => {
+                return self.parse_while(None);
+            }
+            Type::Break => {
+                self.consume_unwrap();
+                Statement::Break { label: self.parse_label() }
+            }
+            Type::Continue => {
+                self.consume_unwrap();
+                Statement::Continue { label: self.parse_label() }
             }
             Type::If => {
                 Statement::If {
                     conditions: {
                         let mut val = Vec::new();
-                        val.push(self.parse_condition_tuple());
-                        loop {
-                            if let Type::Else = self.current_unwrap().token_type {
-                                val.push(self.parse_condition_tuple());
-                            } else {
-                                break;
-                            }
+                        val.push(self.parse_condition_tuple()?);
+                        while let Type::Else | Type::Elif = self.current_unwrap().token_type {
+                            val.push(self.parse_condition_tuple()?);
                         }
                         val
                     }
                 }
             }
-            _ => Statement::Expr { expr: self.parse_expr() },
+            _ => Statement::Expr { expr: self.parse_ternary()? },
+        })
+    }
+
+    /// parses `while cond { ... }`, with any `label:` before it already consumed
+    fn parse_while(&mut self, label: Option<String>) -> Result<Statement, ParseError> {
+        self.expect_consume(Type::While)?;
+        Ok(Statement::While {
+            label,
+            condition: Box::new(self.parse_statement()?),
+            body: Box::new(self.parse_block()?),
+        })
+    }
+
+    /// the loop label after a break or continue, if there is one
+    fn parse_label(&mut self) -> Option<String> {
+        match self.current_unwrap().token_type {
+            Type::Ident => Some(self.consume_unwrap().str),
+            _ => None,
+        }
+    }
+
+    /// parses `func takes (a, b) { ... }`, where the takes list is optional
+    fn parse_function(&mut self) -> Result<Statement, ParseError> {
+        self.expect_consume(Type::FuncDec)?;
+        let mut params = Vec::new();
+        if self.consume_if(Type::FuncParams) {
+            self.expect_consume(Type::OpenGrouper)?;
+            if self.current_unwrap().token_type != Type::CloseGrouper {
+                loop {
+                    let name = self.expect_consume(Type::Ident)?.str;
+                    let annotation = match self.consume_if(Type::Colon) {
+                        true => Some(self.parse_annotation()?),
+                        false => None,
+                    };
+                    params.push(Param { name, annotation });
+                    if !self.consume_if(Type::Separator) {
+                        break;
+                    }
+                }
+            }
+            self.expect_consume(Type::CloseGrouper)?;
         }
+        Ok(Statement::FunctionDec {
+            params,
+            body: Box::new(self.parse_block()?),
+        })
     }
 
-    fn parse_condition_tuple(&mut self) -> (Option<Statement>, Statement) {
+    /// a type name as the `type` builtin spells it; int and bool are accepted for integer and boolean
+    fn parse_annotation(&mut self) -> Result<String, ParseError> {
+        let name = match self.current_unwrap().str.as_str() {
+            "int" | "integer" => "integer",
+            "bool" | "boolean" => "boolean",
+            name @ ("string" | "float" | "list" | "map" | "function" | "null") => name,
+            _ => return Err(self.error("a type name")),
+        }.to_string();
+        self.expect_consume(Type::Ident)?;
+        Ok(name)
+    }
+
+    fn parse_condition_tuple(&mut self) -> Result<(Option<Statement>, Statement), ParseError> {
         match self.current_unwrap().token_type {
-            Type::If => {
+            Type::If | Type::Elif => { // elif is shorthand for else if
                 self.consume_unwrap();
-                let condition = self.parse_statement();
-                self.expect_consume(Type::Then);
-                let consequent = self.parse_statement();
-                (Some(condition), consequent)
+                let condition = self.parse_statement()?;
+                self.expect_consume(Type::Then)?;
+                let consequent = self.parse_statement()?;
+                Ok((Some(condition), consequent))
             },
             Type::Else => {
                 self.consume_unwrap();
                 if self.current_unwrap().token_type == Type::If {
                     self.parse_condition_tuple()
                 } else {
-                    (None, self.parse_statement())
+                    Ok((None, self.parse_statement()?))
                 }
             },
-            _ => panic!("Error parsing condition tuple at token: {:?}, expected If or Else token", self.current_unwrap()),
+            _ => Err(self.error("an If, Elif, or Else token")),
         }
     }
 
-    fn parse_expr(&mut self) -> Expr {
-        let mut terms = vec![self.parse_term()];
+    /// parses `value if condition else alternative`, or a plain expression if no `if` follows
+    fn parse_ternary(&mut self) -> Result<Expr, ParseError> {
+        let value = self.parse_or()?;
+        if !self.consume_if(Type::If) {
+            return Ok(value);
+        }
+        let condition = self.parse_or()?;
+        self.expect_consume(Type::Else)?;
+        let alternative = self.parse_ternary()?;
+        let statement = Statement::If {
+            conditions: vec![
+                (Some(Statement::Expr { expr: condition }), Statement::Expr { expr: value }),
+                (None, Statement::Expr { expr: alternative }),
+            ]
+        };
+        Ok(statement_expr(statement))
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_and()?;
+        while self.consume_if(Type::Or) {
+            let right = self.parse_and()?;
+            left = statement_expr(Statement::Logical { left, op: LogicOp::Or, right });
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_not()?;
+        while self.consume_if(Type::And) {
+            let right = self.parse_not()?;
+            left = statement_expr(Statement::Logical { left, op: LogicOp::And, right });
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, ParseError> {
+        if self.consume_if(Type::Not) {
+            let expr = self.parse_not()?;
+            Ok(statement_expr(Statement::Not { expr }))
+        } else {
+            self.parse_comparison()
+        }
+    }
+
+    /// parses `left < right` and the other comparisons, which don't chain
+    fn parse_comparison(&mut self) -> Result<Expr, ParseError> {
+        let left = self.parse_expr()?;
+        let op = match self.current_unwrap().token_type {
+            Type::Eq => CompOp::Eq,
+            Type::Neq => CompOp::Neq,
+            Type::Lt => CompOp::Lt,
+            Type::Gt => CompOp::Gt,
+            Type::Lte => CompOp::Lte,
+            Type::Gte => CompOp::Gte,
+            _ => return Ok(left),
+        };
+        self.consume_unwrap(); // consume operator
+        let right = self.parse_expr()?;
+        Ok(statement_expr(Statement::Comparison { left, op, right }))
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut terms = vec![self.parse_term()?];
         let mut ops = Vec::new();
         loop {
             match self.current_unwrap().token_type {
@@ -198,17 +515,17 @@ impl Parser {
                 _ => break,
             }
             self.consume_unwrap(); // consume operator
-            terms.push(self.parse_term());
+            terms.push(self.parse_term()?);
         };
-        Expr {
+        Ok(Expr {
             terms,
             ops,
-        }
+        })
     }
 
-    fn parse_term(&mut self) -> Term {
+    fn parse_term(&mut self) -> Result<Term, ParseError> {
         let mut term = Term {
-            factors: vec![self.parse_factor()],
+            factors: vec![self.parse_factor()?],
             ops: Vec::new(),
         };
         loop {
@@ -219,21 +536,46 @@ impl Parser {
                 Type::Div => {
                     term.ops.push(TermOp::Div);
                 }
+                Type::Mod => {
+                    term.ops.push(TermOp::Mod);
+                }
                 _ => break,
             }
             self.consume_unwrap(); // consume operator
-            term.factors.push(self.parse_factor());
+            term.factors.push(self.parse_factor()?);
         };
-        term
+        Ok(term)
     }
 
-    fn parse_factor(&mut self) -> Factor {
-        match self.current_unwrap().token_type {
-            Type::Integer => Factor::IntFactor(self.consume_unwrap().str.trim().parse::<i32>()
-                .unwrap_or_else(|_| panic!("Failed to parse integer at token {:?}", self.current_unwrap()))),
+    fn parse_factor(&mut self) -> Result<Factor, ParseError> {
+        let mut factor = self.parse_primary()?;
+        while self.consume_if(Type::OpenBracket) {
+            let index = self.parse_ternary()?;
+            self.expect_consume(Type::CloseBracket)?;
+            factor = Factor::IndexFactor(Box::new(factor), Box::new(index));
+        }
+        Ok(factor)
+    }
+
+    fn parse_primary(&mut self) -> Result<Factor, ParseError> {
+        Ok(match self.current_unwrap().token_type {
+            Type::Integer => match self.current_unwrap().str.trim().parse::<i32>() {
+                Ok(num) => {
+                    self.consume_unwrap();
+                    Factor::IntFactor(num)
+                }
+                Err(_) => return Err(self.error("an integer that fits in 32 bits")),
+            },
+            Type::Float => match self.current_unwrap().str.parse::<f64>() {
+                Ok(num) => {
+                    self.consume_unwrap();
+                    Factor::FloatFactor(num)
+                }
+                Err(_) => return Err(self.error("a float")),
+            },
             Type::String => Factor::StringFactor({
                 let full = self.consume_unwrap().str;
-                String::from(&full[1..full.len() - 1]) // remove start and end quotes
+                unescape(&full[1..full.len() - 1]) // remove start and end quotes
             }),
             Type::True => {
                 self.consume_unwrap();
@@ -247,44 +589,65 @@ impl Parser {
             Type::OpenGrouper => {
                 self.consume_unwrap(); // consume parentheses
                 let factor: Factor = Factor::StmtFactor(Box::new(
-                    self.parse_statement()
+                    self.parse_statement()?
                 ));
-                self.expect_consume(Type::CloseGrouper);
+                self.expect_consume(Type::CloseGrouper)?;
                 factor
             }
             Type::BlockStart => {
-                Factor::StmtFactor(Box::new(self.parse_block()))
+                Factor::StmtFactor(Box::new(self.parse_block()?))
+            }
+            Type::OpenBracket => {
+                self.consume_unwrap(); // consume [
+                let mut elements = Vec::new();
+                if self.current_unwrap().token_type != Type::CloseBracket {
+                    loop {
+                        elements.push(self.parse_ternary()?);
+                        if !self.consume_if(Type::Separator) {
+                            break;
+                        }
+                    }
+                }
+                self.expect_consume(Type::CloseBracket)?;
+                Factor::ListFactor(elements)
+            }
+            Type::FuncDec => {
+                Factor::StmtFactor(Box::new(self.parse_function()?))
             }
             Type::FunctionCall => {
                 self.consume_unwrap(); // consume FunctionCall
                 Factor::StmtFactor(Box::new(Statement::FunctionCall {
-                    func: Box::new(self.parse_statement()),
+                    func: Box::new(self.parse_statement()?),
                     args: { // build arguments
                         let mut val = Vec::new();
                         if self.consume_if(Type::ParameterList) {
-                            self.expect_consume(Type::OpenGrouper); // consume opening paren of args
+                            self.expect_consume(Type::OpenGrouper)?; // consume opening paren of args
                             loop {
-                                val.push(self.parse_statement());
+                                val.push(self.parse_statement()?);
                                 if !self.consume_if(Type::Separator) {
                                     break;
                                 }
                             }
-                            self.expect_consume(Type::CloseGrouper); // consume closing paren
+                            self.expect_consume(Type::CloseGrouper)?; // consume closing paren
                         }
                         val
                     },
                 }))
             }
-            _ => panic!("Tried to parse factor but token {:?} is not of type Num, String, Ident, OpenGrouper, or FunctionCall", self.current_unwrap()),
-        }
+            _ => return Err(self.error("a number, string, identifier, '(', '[', block, or function call")),
+        })
     }
 
-    fn expect_consume(&mut self, consume_type: Type) -> Token {
-        let got = self.consume_unwrap();
-        if got.token_type != consume_type {
-            panic!("Expected {:?}, got {:?}", consume_type, got);
+    fn expect_consume(&mut self, consume_type: Type) -> Result<Token, ParseError> {
+        if self.current_unwrap().token_type != consume_type {
+            return Err(self.error(&format!("{:?}", consume_type)));
         }
-        got
+        Ok(self.consume_unwrap())
+    }
+
+    /// an error at the current token, saying what was expected there
+    fn error(&self, expected: &str) -> ParseError {
+        ParseError { expected: String::from(expected), found: self.current_unwrap() }
     }
 
     fn consume_if(&mut self, consume_type: Type) -> bool {
@@ -296,14 +659,7 @@ impl Parser {
         }
     }
 
-    fn consume(&mut self) -> Option<Token> {
-        let val = self.current();
-        self.increment();
-        val
-    }
-
     fn consume_unwrap(&mut self) -> Token {
-        // dummy token after calling self.error (self.error will panic)
         let val = self.current_unwrap();
         self.increment();
         val
@@ -321,7 +677,110 @@ impl Parser {
         self.current().unwrap_or_else(|| panic!("Ran out of tokens to parse"))
     }
 
+    fn peek_type(&self) -> Option<Type> {
+        self.tokens.get(self.index + 1).map(|token| token.token_type.clone())
+    }
+
     fn increment(&mut self) {
         self.index += 1;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::tokenize;
+
+    fn parse(source: &str) -> Vec<Statement> {
+        Parser::new(tokenize(source).unwrap()).parse_lines().unwrap_or_else(|err| panic!("{} failed: {}", source, err))
+    }
+
+    /// an expression that is just the factor
+    fn expr(factor: Factor) -> Expr {
+        Expr { terms: vec![Term { factors: vec![factor], ops: Vec::new() }], ops: Vec::new() }
+    }
+
+    fn ident(name: &str) -> Expr {
+        expr(Factor::IdentFactor(String::from(name)))
+    }
+
+    #[test]
+    fn ternary_is_a_two_branch_if() {
+        let conditions = vec![
+            (Some(Statement::Expr { expr: ident("c") }), Statement::Expr { expr: ident("a") }),
+            (None, Statement::Expr { expr: ident("b") }),
+        ];
+        assert_eq!(parse("set x to a if c else b"), vec![Statement::Assign {
+            ident: String::from("x"),
+            expr: statement_expr(Statement::If { conditions }),
+            change: false,
+        }]);
+    }
+
+    #[test]
+    fn elif_is_the_same_as_else_if() {
+        let conditions = vec![
+            (Some(Statement::Expr { expr: ident("a") }), Statement::Expr { expr: ident("x") }),
+            (Some(Statement::Expr { expr: ident("b") }), Statement::Expr { expr: ident("y") }),
+            (None, Statement::Expr { expr: ident("z") }),
+        ];
+        assert_eq!(parse("if a then x elif b then y else z"), vec![Statement::If { conditions }]);
+        assert_eq!(parse("if a then x elif b then y else z"), parse("if a then x else if b then y else z"));
+    }
+
+    #[test]
+    fn increment_and_decrement_change_the_variable() {
+        let change = |op| Statement::Assign {
+            ident: String::from("x"),
+            expr: Expr {
+                terms: vec![
+                    Term { factors: vec![Factor::IdentFactor(String::from("x"))], ops: Vec::new() },
+                    Term { factors: vec![Factor::IntFactor(1)], ops: Vec::new() },
+                ],
+                ops: vec![op],
+            },
+            change: true,
+        };
+        assert_eq!(parse("x++; x--"), vec![change(ExprOp::Add), change(ExprOp::Sub)]);
+    }
+
+    #[test]
+    fn newlines_can_terminate_statements() {
+        let program = |tokens| Parser::new(tokens).parse().unwrap().body;
+        let newlines = "{\n    set x to 1 +\n        2\n    x++\n    if x > 2 then {\n        x\n    }\n}\n";
+        let semicolons = "{ set x to 1 + 2; x++; if x > 2 then { x }; }";
+        assert_eq!(program(crate::token::tokenize_newline_terminated(newlines).unwrap()), program(tokenize(semicolons).unwrap()));
+        assert!(Parser::new(tokenize(newlines).unwrap()).parse().is_err()); // off by default
+    }
+
+    #[test]
+    fn parse_errors_give_the_position() {
+        let err = Parser::new(tokenize("{\n  set x to 1\n  set y to 2;\n}").unwrap()).parse().unwrap_err();
+        assert_eq!(err.to_string(), "Expected Terminator at line 3, col 3, got Assignment \"set\"");
+    }
+
+    #[test]
+    fn string_escapes_are_decoded() {
+        let string = |source| match parse(source).remove(0) {
+            Statement::Expr { expr } => expr.terms[0].factors[0].clone(),
+            statement => panic!("expected an expression, got {:?}", statement),
+        };
+        assert_eq!(string(r#""say \"hi\"""#), Factor::StringFactor(String::from("say \"hi\"")));
+        assert_eq!(string(r#""a\tb\nc""#), Factor::StringFactor(String::from("a\tb\nc")));
+        assert_eq!(string(r#""back\\slash""#), Factor::StringFactor(String::from("back\\slash")));
+        assert_eq!(string(r#"'it\'s'"#), Factor::StringFactor(String::from("it's")));
+        assert_eq!(string(r#""\q""#), Factor::StringFactor(String::from("\\q")));
+    }
+
+    #[test]
+    fn function_declarations_with_and_without_parameters() {
+        let param = |name: &str, annotation: Option<&str>| Param { name: String::from(name), annotation: annotation.map(String::from) };
+        let body = |name| Box::new(Statement::BlockStatement { statements: vec![Statement::Expr { expr: ident(name) }] });
+        assert_eq!(parse("func { x }"), vec![Statement::FunctionDec { params: Vec::new(), body: body("x") }]);
+        assert_eq!(parse("func takes () { x }"), vec![Statement::FunctionDec { params: Vec::new(), body: body("x") }]);
+        assert_eq!(parse("func takes (a, b: int) { b }"), vec![Statement::FunctionDec {
+            params: vec![param("a", None), param("b", Some("integer"))],
+            body: body("b"),
+        }]);
+    }
+}
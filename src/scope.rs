@@ -1,61 +1,88 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
 
 use crate::builtins::fill_with_builtins;
 use crate::object::Object;
 
-#[derive(PartialEq, Debug, Clone)]
-pub struct Scope {
+/// a handle to one level of variables and, through its parent, the levels around it.
+/// clones share the same variables, so a function can hold on to the scope it was defined in
+#[derive(Clone)]
+pub struct Scope(Rc<RefCell<Frame>>);
+
+struct Frame {
     memvars: HashMap<String, Object>,
-    parent: Option<Box<Scope>>,
+    parent: Option<Scope>,
 }
 
 impl Scope {
     pub fn new_root() -> Scope {
-        Scope {
+        Scope::from_frame(Frame {
             memvars: {
                 let mut map = HashMap::new();
                 fill_with_builtins(&mut map);
                 map
             },
             parent: None,
-        }
+        })
     }
 
-    pub fn new_empty() -> Scope {
-        Scope {
-            memvars: HashMap::new(),
-            parent: None,
-        }
+    fn from_frame(frame: Frame) -> Scope {
+        Scope(Rc::new(RefCell::new(frame)))
     }
 
-    /// returns a new scope this scope as the parent
-    pub fn extend(self) -> Scope {
-        Scope {
+    /// returns a new scope with this scope as the parent
+    pub fn extend(&self) -> Scope {
+        Scope::from_frame(Frame {
             memvars: HashMap::new(),
-            parent: Some(Box::new(self)),
-        }
+            parent: Some(self.clone()),
+        })
     }
 
-    /// returns the parent scope and destroys this one
-    pub fn retrieve(self) -> Scope {
-        *self.parent.unwrap()
+    /// returns the parent scope; this one lives on only if something else still holds it
+    pub fn retrieve(&self) -> Scope {
+        self.0.borrow().parent.clone().unwrap()
+    }
+
+    /// copies this scope, its parents, and every scope a function in them was defined in, into a chain
+    /// that shares nothing with this one. functions in the copy point at the copied scopes
+    pub fn detached_copy(&self) -> Scope {
+        self.copy_into(&mut HashMap::new())
+    }
+
+    /// copies keeps each scope's copy, so a scope reached twice (or from itself) is only copied once
+    fn copy_into(&self, copies: &mut HashMap<*const RefCell<Frame>, Scope>) -> Scope {
+        if let Some(copy) = copies.get(&Rc::as_ptr(&self.0)) {
+            return copy.clone();
+        }
+        let frame = self.0.borrow();
+        let copy = Scope::from_frame(Frame { memvars: HashMap::new(), parent: None });
+        copies.insert(Rc::as_ptr(&self.0), copy.clone());
+        let parent = frame.parent.as_ref().map(|parent| parent.copy_into(copies));
+        let memvars = frame.memvars.iter()
+            .map(|(name, value)| (name.clone(), copy_object(value, copies)))
+            .collect();
+        *copy.0.borrow_mut() = Frame { memvars, parent };
+        copy
     }
 
-    pub fn set(&mut self, name: &str, value: &Object) {
-        self.memvars.insert(name.to_string(), value.clone());
+    pub fn set(&self, name: &str, value: &Object) {
+        self.0.borrow_mut().memvars.insert(name.to_string(), value.clone());
     }
 
-    pub fn reassign(&mut self, name: &str, value: &Object) -> bool {
-        match self.memvars.get(name) {
-            Some(object) => {
-                self.set(name, value);
+    /// overwrites the variable in the nearest scope that defines it, or returns false if none does
+    pub fn reassign(&self, name: &str, value: &Object) -> bool {
+        let mut frame = self.0.borrow_mut();
+        match frame.memvars.get_mut(name) {
+            Some(slot) => {
+                *slot = value.clone();
                 true
             },
             None => {
-                match &mut self.parent {
+                match &frame.parent {
                     Some(parent) => {
-                        parent.reassign(name, value);
-                        true
+                        parent.reassign(name, value)
                     }
                     None => false,
                 }
@@ -64,10 +91,11 @@ impl Scope {
     }
 
     pub fn get(&self, name: &str) -> Option<Object> {
-        match self.memvars.get(name) {
+        let frame = self.0.borrow();
+        match frame.memvars.get(name) {
             Some(object) => Some(object.clone()),
             None => {
-                match &self.parent {
+                match &frame.parent {
                     Some(parent) => {
                         parent.get(name)
                     }
@@ -76,4 +104,28 @@ impl Scope {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+fn copy_object(obj: &Object, copies: &mut HashMap<*const RefCell<Frame>, Scope>) -> Object {
+    match obj {
+        Object::List(list) => Object::List(list.iter().map(|obj| copy_object(obj, copies)).collect()),
+        Object::Map(map) => Object::Map(map.iter().map(|(key, obj)| (key.clone(), copy_object(obj, copies))).collect()),
+        Object::Function(params, body, captured) => Object::Function(params.clone(), body.clone(), captured.copy_into(copies)),
+        Object::Memoized(func, cache) => Object::Memoized(Box::new(copy_object(func, copies)), cache.clone()),
+        obj => obj.clone(),
+    }
+}
+
+/// two handles are equal when they point at the same scope, not when their variables match
+impl PartialEq for Scope {
+    fn eq(&self, other: &Scope) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// opaque, since a scope can hold a function that holds the scope
+impl fmt::Debug for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<scope>")
+    }
+}
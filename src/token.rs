@@ -1,7 +1,10 @@
-use std::process;
+use std::error::Error;
+use std::fmt;
+use std::sync::OnceLock;
 
 use regex::Regex;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type {
     EOF,
@@ -14,13 +17,26 @@ pub enum Type {
     Assignment,
     OpenGrouper,
     CloseGrouper,
+    OpenBracket,
+    CloseBracket,
     Separator,
+    Colon,
     Terminator,
     Integer,
+    Float,
     Add,
     Sub,
     Mul,
     Div,
+    Mod,
+    Eq,
+    Neq,
+    Lt,
+    Gt,
+    Lte,
+    Gte,
+    Increment,
+    Decrement,
     AssignmentOp,
     Return,
     BlockStart,
@@ -32,88 +48,474 @@ pub enum Type {
     FuncParams,
     Then,
     Else,
+    Elif,
     Change,
+    And,
+    Or,
+    Not,
+    While,
+    Break,
+    Continue,
 }
 
 #[derive(Debug, Clone)]
 pub struct Token {
     pub token_type: Type,
     pub str: String,
+    /// 1-based position of the token's first character
+    pub line: usize,
+    pub col: usize,
 }
 
-pub fn tokenize(program_string: &str) -> Vec<Token> {
-    let expressions: [(Regex, Type); 29] = [
-        (Regex::new(r"^--.*?\n").unwrap(), Type::Comment),
-        (Regex::new(r#"^".*?""#).unwrap(), Type::String),
-        (Regex::new(r"^'.*?'").unwrap(), Type::String),
-        (Regex::new(r"^[0-9]+").unwrap(), Type::Integer),
-        (Regex::new(r"^;").unwrap(), Type::Terminator),
-        (Regex::new(r"^(call)[^A-Za-z0-9_\-]").unwrap(), Type::FunctionCall),
-        (Regex::new(r"^(set)[^A-Za-z0-9_\-]").unwrap(), Type::Assignment),
-        (Regex::new(r"^(change)[^A-Za-z0-9_\-]").unwrap(), Type::Change),
-        (Regex::new(r"^(return)[^A-Za-z0-9_\-]").unwrap(), Type::Return),
-        (Regex::new(r"^(to)[^A-Za-z0-9_\-]").unwrap(), Type::AssignmentOp),
-        (Regex::new(r"^(with)[^A-Za-z0-9_\-]").unwrap(), Type::ParameterList),
-        (Regex::new(r"^(if)[^A-Za-z0-9_\-]").unwrap(), Type::If),
-        (Regex::new(r"^(then)[^A-Za-z0-9_\-]").unwrap(), Type::Then),
-        (Regex::new(r"^(else)[^A-Za-z0-9_\-]").unwrap(), Type::Else),
-        (Regex::new(r"^(true)[^A-Za-z0-9_\-]").unwrap(), Type::True),
-        (Regex::new(r"^(false)[^A-Za-z0-9_\-]").unwrap(), Type::False),
-        (Regex::new(r"^(func)[^A-Za-z0-9_\-]").unwrap(), Type::FuncDec),
-        (Regex::new(r"^(takes)[^A-Za-z0-9_\-]").unwrap(), Type::FuncParams),
-        (Regex::new(r"^\(").unwrap(), Type::OpenGrouper),
-        (Regex::new(r"^\)").unwrap(), Type::CloseGrouper),
-        (Regex::new(r"^\{").unwrap(), Type::BlockStart),
-        (Regex::new(r"^\}").unwrap(), Type::BlockEnd),
-        (Regex::new(r"^,").unwrap(), Type::Separator),
-        (Regex::new(r"^\+").unwrap(), Type::Add),
-        (Regex::new(r"^-").unwrap(), Type::Sub),
-        (Regex::new(r"^\*").unwrap(), Type::Mul),
-        (Regex::new(r"^/").unwrap(), Type::Div),
-        (Regex::new(r"^[A-Za-z_][A-Za-z0-9_\-]*").unwrap(), Type::Ident),
-        (Regex::new(r"^[ \n\t]+").unwrap(), Type::Whitespace),
-    ];
-
-    work(program_string, &expressions)
-}
-
-fn work(characters: &str, expressions: &[(Regex, Type)]) -> Vec<Token> {
+impl Token {
+    pub fn new(token_type: Type, str: &str, line: usize, col: usize) -> Token {
+        Token { token_type, str: String::from(str), line, col }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexErrorKind {
+    /// no lexer rule matches the character
+    UnrecognizedCharacter,
+    /// a `/*` with no `*/` after it
+    UnterminatedComment,
+}
+
+/// where and why the source couldn't be split into tokens
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    /// byte offset into the source
+    pub index: usize,
+    pub line: usize,
+    pub col: usize,
+    pub character: char,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            LexErrorKind::UnrecognizedCharacter => {
+                write!(f, "Unrecognized character {:?} at line {}, col {}", self.character, self.line, self.col)
+            }
+            LexErrorKind::UnterminatedComment => {
+                write!(f, "Block comment starting at line {}, col {} is never closed with */", self.line, self.col)
+            }
+        }
+    }
+}
+
+impl Error for LexError {}
+
+/// a keyword given to Tokenizer::new that couldn't be lexed as one
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeywordError {
+    pub keyword: String,
+}
+
+impl fmt::Display for KeywordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Keyword {:?} is not spelled like an identifier", self.keyword)
+    }
+}
+
+impl Error for KeywordError {}
+
+/// how each keyword is spelled by default; pass a modified copy to Tokenizer::new to rename them
+pub const ENGLISH_KEYWORDS: [(&str, Type); 20] = [
+    ("call", Type::FunctionCall),
+    ("set", Type::Assignment),
+    ("change", Type::Change),
+    ("return", Type::Return),
+    ("to", Type::AssignmentOp),
+    ("with", Type::ParameterList),
+    ("if", Type::If),
+    ("then", Type::Then),
+    ("else", Type::Else),
+    ("elif", Type::Elif),
+    ("true", Type::True),
+    ("false", Type::False),
+    ("func", Type::FuncDec),
+    ("takes", Type::FuncParams),
+    ("and", Type::And),
+    ("or", Type::Or),
+    ("not", Type::Not),
+    ("while", Type::While),
+    ("break", Type::Break),
+    ("continue", Type::Continue),
+];
+
+pub struct Tokenizer {
+    /// every rule as one alternation; the regex engine prefers earlier alternatives, so rules keep their priority
+    combined: Regex,
+    /// in priority order, each rule's capture group, the group holding its token text, and its type
+    rules: Vec<(usize, usize, Type)>,
+}
+
+impl Tokenizer {
+    /// builds a tokenizer that spells keywords as given, e.g. `("si", Type::If)`. each spelling must
+    /// also be a valid identifier, so that a keyword can't match nothing or swallow an operator
+    pub fn new(keywords: &[(&str, Type)]) -> Result<Tokenizer, KeywordError> {
+        let identifier = Regex::new(r"^[A-Za-z_](?:[A-Za-z0-9_\-]*[A-Za-z0-9_])?$").unwrap();
+        if let Some((word, _)) = keywords.iter().find(|(word, _)| !identifier.is_match(word)) {
+            return Err(KeywordError { keyword: String::from(*word) });
+        }
+        let mut alternatives = Vec::new();
+        let mut rules = Vec::new();
+        let mut group = 1;
+        for (pattern, token_type) in Tokenizer::rule_patterns(keywords) {
+            let inner_groups = Regex::new(&pattern).unwrap().captures_len() - 1;
+            alternatives.push(format!("({})", pattern));
+            rules.push((group, if inner_groups > 0 { group + 1 } else { group }, token_type));
+            group += 1 + inner_groups;
+        }
+        let combined = Regex::new(&format!("^(?:{})", alternatives.join("|"))).unwrap();
+        Ok(Tokenizer { combined, rules })
+    }
+
+    /// every rule's pattern and token type, in priority order
+    fn rule_patterns(keywords: &[(&str, Type)]) -> Vec<(String, Type)> {
+        // a rule with a capture group of its own only produces that group, and leaves the rest for the next token
+        let mut patterns = vec![
+            (String::from(r"(--)[ \t]*(?:;|\}|\r?\n|$)"), Type::Decrement), // only where a statement or line ends, see work
+            (String::from(r"--[^\n]*"), Type::Comment), // the newline is left to the whitespace rule
+            (String::from(r"/\*(?s:.)*?\*/"), Type::Comment), // may span lines, and ends at the first */
+            (String::from(r"/\*"), Type::Comment), // only reached when there's no */, see work
+            (String::from(r#""(?:[^"\\\n]|\\.)*""#), Type::String), // a \ escapes the next character
+            (String::from(r"'(?:[^'\\\n]|\\.)*'"), Type::String),
+            (String::from(r"[0-9]+\.[0-9]+"), Type::Float),
+            (String::from(r"[0-9]+"), Type::Integer),
+            (String::from(r";"), Type::Terminator),
+        ];
+        for (word, token_type) in keywords {
+            // a keyword can't run into an identifier character, but may end the file
+            patterns.push((format!(r"({})(?:[^A-Za-z0-9_\-]|$)", regex::escape(word)), token_type.clone()));
+        }
+        patterns.extend(vec![
+            (String::from(r"\("), Type::OpenGrouper),
+            (String::from(r"\)"), Type::CloseGrouper),
+            (String::from(r"\{"), Type::BlockStart),
+            (String::from(r"\}"), Type::BlockEnd),
+            (String::from(r"\["), Type::OpenBracket),
+            (String::from(r"\]"), Type::CloseBracket),
+            (String::from(r","), Type::Separator),
+            (String::from(r":"), Type::Colon),
+            (String::from(r"\+\+"), Type::Increment),
+            (String::from(r"\+"), Type::Add),
+            (String::from(r"-"), Type::Sub),
+            (String::from(r"\*"), Type::Mul),
+            (String::from(r"/"), Type::Div),
+            (String::from(r"%"), Type::Mod),
+            (String::from(r"=="), Type::Eq),
+            (String::from(r"!="), Type::Neq),
+            (String::from(r"<="), Type::Lte),
+            (String::from(r">="), Type::Gte),
+            (String::from(r"<"), Type::Lt),
+            (String::from(r">"), Type::Gt),
+            // an identifier may end in one -, as in x-, but not two, so that x-- lexes as x, --
+            (String::from(r"([A-Za-z_](?:[A-Za-z0-9_\-]*[A-Za-z0-9_])?-)(?:[^A-Za-z0-9_\-]|$)"), Type::Ident),
+            (String::from(r"[A-Za-z_](?:[A-Za-z0-9_\-]*[A-Za-z0-9_])?"), Type::Ident),
+            (String::from(r"[ \n\t]+"), Type::Whitespace),
+        ]);
+        patterns
+    }
+
+    pub fn tokenize(&self, program_string: &str) -> Result<Vec<Token>, LexError> {
+        work(program_string, self, false)
+    }
+
+    /// like tokenize, but a newline directly after a token that can end a statement (an identifier,
+    /// literal, `)`, `]`, `}`, `++`, `--`, `break` or `continue`) also acts as a `;`. lines can still be
+    /// continued after an operator or `,`, but not before one, so `} else {` must stay on one line
+    pub fn tokenize_newline_terminated(&self, program_string: &str) -> Result<Vec<Token>, LexError> {
+        work(program_string, self, true)
+    }
+}
+
+impl Default for Tokenizer {
+    fn default() -> Tokenizer {
+        Tokenizer::new(&ENGLISH_KEYWORDS).unwrap() // the English spellings are all identifiers
+    }
+}
+
+/// the English tokenizer, compiled the first time it's needed and shared after that
+fn default_tokenizer() -> &'static Tokenizer {
+    static DEFAULT: OnceLock<Tokenizer> = OnceLock::new();
+    DEFAULT.get_or_init(Tokenizer::default)
+}
+
+/// tokenizes with the default English keywords
+pub fn tokenize(program_string: &str) -> Result<Vec<Token>, LexError> {
+    default_tokenizer().tokenize(program_string)
+}
+
+/// see Tokenizer::tokenize_newline_terminated
+pub fn tokenize_newline_terminated(program_string: &str) -> Result<Vec<Token>, LexError> {
+    default_tokenizer().tokenize_newline_terminated(program_string)
+}
+
+fn work(characters: &str, tokenizer: &Tokenizer, newline_terminators: bool) -> Result<Vec<Token>, LexError> {
     let mut token_list: Vec<Token> = Vec::new();
-    let mut str_index: usize = 0;
+    // skip a #! line so scripts can be made executable, but only at the very start of the file
+    let mut str_index: usize = if characters.starts_with("#!") {
+        characters.find('\n').unwrap_or(characters.len())
+    } else {
+        0
+    };
+    let (mut line, mut col) = (1, characters[..str_index].chars().count() + 1);
     while str_index < characters.len() {
-        let mut found = false;
-        for (regex, token_type) in expressions {
-//            println!("Searching at index: {}", str_index);
-            if let Some(groups) = regex.captures(&characters[str_index..]) {
-                let re_match = match groups.len() {
-                    1 => groups.get(0).unwrap(),
-                    _ => groups.get(1).unwrap(),
-                };
-                let str = String::from(&characters[re_match.start() + str_index..re_match.end() + str_index]);
-//                    println!("Found match: {}", str);
-                match token_type {
-                    Type::Comment | Type::Whitespace => (), // don't add comments and whitespace to token list
-                    _ => { // add everything else
-                        token_list.push(Token {
-                            token_type: token_type.clone(),
-                            str,
-                        });
-                    }
+        let groups = match tokenizer.combined.captures(&characters[str_index..]) {
+            Some(groups) => groups,
+            None => return Err(unrecognized_character(characters, str_index, line, col)),
+        };
+        // exactly one rule's group takes part in the match
+        let (text_group, token_type) = tokenizer.rules.iter()
+            .find(|(group, _, _)| groups.get(*group).is_some())
+            .map(|(_, text_group, token_type)| (*text_group, token_type))
+            .unwrap();
+        let re_match = groups.get(text_group).unwrap();
+        let (mut token_type, mut end) = (token_type, re_match.end());
+        if end == 0 {
+            // no rule should match nothing, but if one did the lexer would never move on
+            return Err(unrecognized_character(characters, str_index, line, col));
+        }
+        if *token_type == Type::Decrement
+            && !token_list.last().is_some_and(|token| token.token_type == Type::Ident && token.line == line) {
+            // only x-- on one line is a decrement; a -- anywhere else starts a comment, even one like `-- }`
+            token_type = &Type::Comment;
+            end = characters[str_index..].find('\n').unwrap_or(characters.len() - str_index);
+        }
+        let str = String::from(&characters[re_match.start() + str_index..end + str_index]);
+        match token_type {
+            Type::Comment if str == "/*" => {
+                return Err(LexError { kind: LexErrorKind::UnterminatedComment, index: str_index, line, col, character: '/' });
+            }
+            Type::Whitespace if newline_terminators && str.contains('\n') => {
+                if token_list.last().is_some_and(|token| ends_statement(token.token_type.clone())) {
+                    token_list.push(Token::new(Type::Terminator, "\n", line, col));
                 }
-                str_index += re_match.end();
-                found = true;
-                break;
             }
-        };
-        if !found {
-            tokenize_error(&characters, str_index);
-        };
+            Type::Comment | Type::Whitespace => (), // don't add comments and whitespace to token list
+            _ => { // add everything else
+                token_list.push(Token {
+                    token_type: token_type.clone(),
+                    str,
+                    line,
+                    col,
+                });
+            }
+        }
+        for c in characters[str_index..str_index + end].chars() {
+            if c == '\n' {
+                line += 1;
+                col = 1;
+            } else {
+                col += 1;
+            }
+        }
+        str_index += end; // regex match offsets always fall on char boundaries
+        debug_assert!(characters.is_char_boundary(str_index));
+    }
+    if newline_terminators && token_list.last().is_some_and(|token| token.str == "\n") {
+        token_list.pop(); // the program ends at its closing brace, not a terminator
     }
-    token_list.push(Token { token_type: Type::EOF, str: String::from("") });
-    token_list
+    token_list.push(Token::new(Type::EOF, "", line, col));
+    Ok(token_list)
 }
 
-fn tokenize_error(characters: &str, index: usize) {
-    println!("Unrecognized character at index {} ({:?}):\n", index, &characters[index..index + 1]);
-    process::exit(1);
-}
\ No newline at end of file
+fn unrecognized_character(characters: &str, index: usize, line: usize, col: usize) -> LexError {
+    LexError {
+        kind: LexErrorKind::UnrecognizedCharacter,
+        index,
+        line,
+        col,
+        character: characters[index..].chars().next().unwrap(), // the whole char, which may be several bytes
+    }
+}
+
+/// whether a newline after this token ends the statement when newlines are terminators
+fn ends_statement(token_type: Type) -> bool {
+    matches!(token_type, Type::Ident | Type::Integer | Type::Float | Type::String | Type::True | Type::False
+        | Type::CloseGrouper | Type::CloseBracket | Type::BlockEnd | Type::Increment | Type::Decrement
+        | Type::Break | Type::Continue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// every construct the lexer knows, for comparing rule choices
+    const EVERY_TOKEN: &str = "#!/usr/bin/env suro\n{ set x- to 1.5 + 2 - 3 * 4 / 5 % 6; change x to 'a\\'b' ; \
+        outer: while not (x <= 1 or x >= 2 and x == 3) { x++; y--; if x != 0 then break outer elif x < 1 then continue \
+        else { return call f with ([1, 2][0], \"s\") } }; set g to func takes (n: int) { n }; /* block\n */ -- line\n}";
+
+    /// each rule choice the lexer makes over the source, given a way to pick the rule at an index
+    fn rule_choices(source: &str, choose: impl Fn(&str) -> (Type, usize, usize)) -> Vec<(Type, usize, usize)> {
+        let mut choices = Vec::new();
+        let mut index = if source.starts_with("#!") { source.find('\n').unwrap() } else { 0 };
+        while index < source.len() {
+            let (token_type, start, end) = choose(&source[index..]);
+            choices.push((token_type, index + start, index + end));
+            index += end;
+        }
+        choices
+    }
+
+    /// how the lexer chose before the rules were combined: each rule's own anchored regex, tried in order
+    fn sequential_choices(source: &str) -> Vec<(Type, usize, usize)> {
+        let rules = Tokenizer::rule_patterns(&ENGLISH_KEYWORDS).into_iter()
+            .map(|(pattern, token_type)| (Regex::new(&format!("^(?:{})", pattern)).unwrap(), token_type))
+            .collect::<Vec<_>>();
+        rule_choices(source, |rest| rules.iter().find_map(|(regex, token_type)| {
+            let groups = regex.captures(rest)?;
+            let re_match = groups.get(groups.len().min(2) - 1).unwrap();
+            Some((token_type.clone(), re_match.start(), re_match.end()))
+        }).unwrap())
+    }
+
+    fn combined_choices(source: &str) -> Vec<(Type, usize, usize)> {
+        let tokenizer = default_tokenizer();
+        rule_choices(source, |rest| {
+            let groups = tokenizer.combined.captures(rest).unwrap();
+            let (_, text_group, token_type) = tokenizer.rules.iter().find(|(group, _, _)| groups.get(*group).is_some()).unwrap();
+            let re_match = groups.get(*text_group).unwrap();
+            (token_type.clone(), re_match.start(), re_match.end())
+        })
+    }
+
+    fn types(source: &str) -> Vec<Type> {
+        tokenize(source).unwrap().into_iter().map(|token| token.token_type).collect()
+    }
+
+    #[test]
+    fn decrement_before_block_end_or_end_of_input() {
+        assert_eq!(types("{ x-- }"), vec![Type::BlockStart, Type::Ident, Type::Decrement, Type::BlockEnd, Type::EOF]);
+        assert_eq!(types("x--"), vec![Type::Ident, Type::Decrement, Type::EOF]);
+        assert_eq!(types("x--;"), vec![Type::Ident, Type::Decrement, Type::Terminator, Type::EOF]);
+    }
+
+    #[test]
+    fn decrement_at_the_end_of_a_line() {
+        assert_eq!(types("{\n x--\n}"), vec![Type::BlockStart, Type::Ident, Type::Decrement, Type::BlockEnd, Type::EOF]);
+        // an empty comment on the line after an identifier stays a comment
+        assert_eq!(types("x\n--\n"), vec![Type::Ident, Type::EOF]);
+    }
+
+    #[test]
+    fn double_hyphen_not_after_identifier_is_a_comment() {
+        assert_eq!(types("{ x; -- }\n}"), vec![Type::BlockStart, Type::Ident, Type::Terminator, Type::BlockEnd, Type::EOF]);
+    }
+
+    #[test]
+    fn identifier_may_end_in_one_hyphen() {
+        let tokens = tokenize("x- a-b x--").unwrap();
+        let strs = tokens.iter().map(|token| token.str.as_str()).collect::<Vec<_>>();
+        assert_eq!(strs, vec!["x-", "a-b", "x", "--", ""]);
+    }
+
+    #[test]
+    fn multi_byte_characters_in_strings_and_comments() {
+        let tokens = tokenize("{ set s to \"héllo 🎉\"; -- café — naïve\n /* ✓ */ s }").unwrap();
+        let strs = tokens.iter().map(|token| token.str.as_str()).collect::<Vec<_>>();
+        assert_eq!(strs, vec!["{", "set", "s", "to", "\"héllo 🎉\"", ";", "s", "}", ""]);
+        assert_eq!((tokens[6].line, tokens[6].col), (2, 10)); // columns count characters, not bytes
+    }
+
+    #[test]
+    fn multi_byte_unrecognized_character_is_reported_whole() {
+        let err = tokenize("set café to 1").unwrap_err();
+        assert_eq!((err.kind, err.index, err.character), (LexErrorKind::UnrecognizedCharacter, 7, 'é'));
+    }
+
+    #[test]
+    fn keyword_at_end_of_input() {
+        assert_eq!(types("set x to true"), vec![Type::Assignment, Type::Ident, Type::AssignmentOp, Type::True, Type::EOF]);
+        assert_eq!(types("return"), vec![Type::Return, Type::EOF]);
+    }
+
+    #[test]
+    fn comment_at_end_of_input() {
+        assert_eq!(types("{ x }\n-- the end"), vec![Type::BlockStart, Type::Ident, Type::BlockEnd, Type::EOF]);
+        assert_eq!(types("{ x } --"), vec![Type::BlockStart, Type::Ident, Type::BlockEnd, Type::EOF]);
+    }
+
+    #[test]
+    fn combined_regex_chooses_rules_like_trying_them_in_order() {
+        for source in [include_str!("../program.suro"), EVERY_TOKEN] {
+            assert_eq!(combined_choices(source), sequential_choices(source));
+        }
+        assert!(tokenize(EVERY_TOKEN).is_ok());
+    }
+
+    #[test]
+    fn unrecognized_character_reports_its_position() {
+        let err = tokenize("{\n    set x to @;\n}").unwrap_err();
+        assert_eq!(err.kind, LexErrorKind::UnrecognizedCharacter);
+        assert_eq!((err.index, err.line, err.col, err.character), (15, 2, 14, '@'));
+    }
+
+    #[test]
+    fn shebang_line_is_skipped() {
+        let tokens = tokenize("#!/usr/bin/env suro\n{ x }").unwrap();
+        let types = tokens.iter().map(|token| token.token_type.clone()).collect::<Vec<_>>();
+        assert_eq!(types, vec![Type::BlockStart, Type::Ident, Type::BlockEnd, Type::EOF]);
+        assert_eq!((tokens[0].line, tokens[0].col), (2, 1));
+        assert!(tokenize("{ x }\n#!/usr/bin/env suro").is_err());
+    }
+
+    #[test]
+    fn keywords_can_be_renamed() {
+        let mut keywords = ENGLISH_KEYWORDS.clone();
+        for (word, token_type) in keywords.iter_mut() {
+            match token_type {
+                Type::If => *word = "si",
+                Type::Then => *word = "entonces",
+                _ => (),
+            }
+        }
+        let tokenizer = Tokenizer::new(&keywords).unwrap();
+        let types = tokenizer.tokenize("si x entonces y").unwrap().into_iter().map(|token| token.token_type).collect::<Vec<_>>();
+        assert_eq!(types, vec![Type::If, Type::Ident, Type::Then, Type::Ident, Type::EOF]);
+        let if_is_a_name = tokenizer.tokenize("if").unwrap();
+        assert_eq!(if_is_a_name[0].token_type, Type::Ident);
+    }
+
+    #[test]
+    fn keywords_must_be_spelled_like_identifiers() {
+        for spelling in ["", "two words", "+", "1st"] {
+            let mut keywords = ENGLISH_KEYWORDS.clone();
+            keywords[0].0 = spelling;
+            let err = Tokenizer::new(&keywords).err().unwrap();
+            assert_eq!(err, KeywordError { keyword: String::from(spelling) });
+        }
+    }
+
+    #[test]
+    fn tokens_know_their_line_and_column() {
+        let tokens = tokenize("{\n  set x to 10;\n}").unwrap();
+        let positions = tokens.iter().map(|token| (token.line, token.col)).collect::<Vec<_>>();
+        assert_eq!(positions, vec![(1, 1), (2, 3), (2, 7), (2, 9), (2, 12), (2, 14), (3, 1), (3, 2)]);
+    }
+
+    #[test]
+    fn default_tokenizer_is_compiled_once() {
+        let first = default_tokenizer();
+        tokenize(&"set x to x + 1;\n".repeat(1000)).unwrap();
+        assert!(std::ptr::eq(first, default_tokenizer()));
+    }
+
+    #[test]
+    fn block_comment_may_span_lines() {
+        let tokens = tokenize("{ /* one\ntwo\nthree */ x }").unwrap();
+        let types = tokens.iter().map(|token| token.token_type.clone()).collect::<Vec<_>>();
+        assert_eq!(types, vec![Type::BlockStart, Type::Ident, Type::BlockEnd, Type::EOF]);
+        assert_eq!((tokens[1].line, tokens[1].col), (3, 10));
+    }
+
+    #[test]
+    fn unterminated_block_comment_is_an_error() {
+        let err = tokenize("{ x }\n  /* never closed\n").unwrap_err();
+        assert_eq!((err.kind.clone(), err.line, err.col), (LexErrorKind::UnterminatedComment, 2, 3));
+        assert_eq!(err.to_string(), "Block comment starting at line 2, col 3 is never closed with */");
+    }
+}
@@ -0,0 +1,24 @@
+use suro::interpreter::Interpreter;
+use suro::object::Object;
+use suro::{eval_in, run, SuroError};
+
+#[test]
+fn run_returns_the_value() {
+    assert_eq!(run("set x to 2 + 3; return x").unwrap(), Object::Integer(5));
+    assert_eq!(run("set x to 2 + 3; x * 2").unwrap(), Object::Integer(10));
+}
+
+#[test]
+fn eval_in_keeps_state_between_calls() {
+    let mut interpreter = Interpreter::new();
+    eval_in(&mut interpreter, "set x to 2").unwrap();
+    eval_in(&mut interpreter, "change x to x + 1").unwrap();
+    assert_eq!(eval_in(&mut interpreter, "x").unwrap(), Object::Integer(3));
+}
+
+#[test]
+fn each_stage_has_its_own_error() {
+    assert!(matches!(run("set x to @"), Err(SuroError::Lex(_))));
+    assert!(matches!(run("set x to"), Err(SuroError::Parse(_))));
+    assert!(matches!(run("1 / 0"), Err(SuroError::Runtime(_))));
+}
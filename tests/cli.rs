@@ -0,0 +1,78 @@
+use std::fs;
+use std::process::{Command, Output};
+
+fn suro(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_suro")).args(args).output().unwrap()
+}
+
+/// runs the source as a script file, with the flags before the file name and the script's arguments after it
+fn suro_script(name: &str, source: &str, flags: &[&str], args: &[&str]) -> Output {
+    let path = std::env::temp_dir().join(format!("suro-{}-{}.suro", name, std::process::id()));
+    fs::write(&path, source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_suro")).args(flags).arg(&path).args(args).output().unwrap();
+    fs::remove_file(&path).unwrap();
+    output
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn eval_prints_the_final_value() {
+    assert_eq!(stdout(&suro(&["-e", "2 + 2"])), "4\n");
+    assert_eq!(stdout(&suro(&["-e", "set x to 3; x * x"])), "9\n");
+}
+
+#[test]
+fn eval_prints_nothing_for_null() {
+    assert_eq!(stdout(&suro(&["-e", "set x to 3"])), "");
+}
+
+#[test]
+fn output_json_replaces_the_auto_print() {
+    assert_eq!(stdout(&suro(&["-e", "2 * 3", "--output-json"])), "6\n");
+    assert_eq!(stdout(&suro(&["-e", "\"a\"", "--output-json"])), "\"a\"\n");
+}
+
+#[test]
+fn trailing_arguments_reach_the_script() {
+    let output = suro_script("args", "{ call print with (call args) }", &[], &["a", "b", "c"]);
+    assert_eq!(stdout(&output), "[a, b, c]\n");
+}
+
+#[test]
+fn runtime_error_exits_non_zero() {
+    let output = suro(&["-e", "missing"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "error: Identifier not found in current scope: missing\n");
+}
+
+#[test]
+fn output_json_serializes_a_script_result() {
+    let output = suro_script("json", "{ set xs to [1.5, \"a\\\"b\", true]; xs }", &["--output-json"], &[]);
+    assert_eq!(stdout(&output), "[1.5,\"a\\\"b\",true]\n");
+    let output = suro_script("json-function", "{ [1, func { 1 }] }", &["--output-json"], &[]);
+    assert_eq!(stdout(&output), "[1,null]\n");
+}
+
+#[test]
+fn print_shows_floats() {
+    assert_eq!(stdout(&suro(&["-e", "call print with (1.5 + 0.25, 2.0, 1 + 0.5)"])), "1.75\n2.0\n1.5\n");
+}
+
+#[test]
+fn print_shows_a_comparison() {
+    assert_eq!(stdout(&suro(&["-e", "call print with (3 < 5)"])), "true\n");
+}
+
+#[test]
+fn print_shows_lists() {
+    assert_eq!(stdout(&suro(&["-e", "call print with ([1, 2, 3])"])), "[1, 2, 3]\n");
+}
+
+#[test]
+fn print_result_is_off_by_default_for_files() {
+    assert_eq!(stdout(&suro_script("quiet", "{ 2 + 2 }", &[], &[])), "");
+    assert_eq!(stdout(&suro_script("print-result", "{ 2 + 2 }", &["-p"], &[])), "4\n");
+}
@@ -0,0 +1,55 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// runs the REPL with the lines piped to stdin, and returns (stdout, stderr)
+fn repl(input: &str) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_suro"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+}
+
+#[test]
+fn variables_persist_between_lines() {
+    let (stdout, stderr) = repl("set x to 5\ncall print with (x)\n");
+    assert_eq!(stdout, "5\n");
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn errors_do_not_end_the_session() {
+    let (stdout, stderr) = repl("set x to 2\nmissing\nset y to\nx + 1\n");
+    assert_eq!(stdout, "3\n");
+    assert_eq!(stderr.lines().count(), 2);
+    assert!(stderr.starts_with("error: Identifier not found in current scope: missing\n"));
+}
+
+#[test]
+fn load_runs_a_file_into_the_session() {
+    let path = std::env::temp_dir().join(format!("suro-load-{}.suro", std::process::id()));
+    fs::write(&path, "{ set double to func takes (x) { x * 2 }; }").unwrap();
+    let (stdout, stderr) = repl(&format!(".load {}\ncall double with (21)\n", path.display()));
+    fs::remove_file(&path).unwrap();
+    assert_eq!(stdout, "42\n");
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn load_error_does_not_end_the_session() {
+    let (stdout, stderr) = repl(".load /no/such/file.suro\n1 + 1\n");
+    assert_eq!(stdout, "2\n");
+    assert!(stderr.starts_with("error: Cannot read /no/such/file.suro"), "{}", stderr);
+}
+
+#[test]
+fn decrement_works_on_its_own_line() {
+    let (stdout, stderr) = repl("set x to 3\nx--\nx\n");
+    assert_eq!(stdout, "2\n");
+    assert_eq!(stderr, "");
+}